@@ -1,15 +1,409 @@
-use crate::{db::Db, Error, Roots};
-use llvm_ir_analysis::{llvm_ir::Module, ModuleAnalysis};
+use crate::{
+    compile::{self, CompileOptions},
+    crate_fs::{self, CrateCache, CrateEntry},
+    db::Db,
+    graph::{crate_name, is_panic_function, CallGraph, FunctionNode, SourceLocation},
+    Error, Roots,
+};
+use llvm_ir_analysis::{
+    llvm_ir::{
+        function::Parameter,
+        instruction::{Call, Load, Store},
+        Constant, Instruction, Module, Name, Operand, Terminator,
+    },
+    ModuleAnalysis,
+};
 use rayon::prelude::*;
+use regex::Regex;
 use rustc_demangle::demangle;
 
 use crates_index::Crate;
-use std::{io::Write, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 const BLOCKED_STRINGS: &[&str] = &["llvm.", "__rust", "rt::", "std::", "core::", "alloc::"];
 
+/// Returns the paths of all `.bc` files directly within `crate_bc_dir`.
+///
+/// # Panics
+/// This function will panic if reading the directory fails.
+fn bc_files(crate_bc_dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(crate_bc_dir)
+        .unwrap()
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some() && p.extension().unwrap() == "bc")
+        .collect()
+}
+
+/// Returns true if calls to or from `name` should be dropped: it contains one of
+/// `BLOCKED_STRINGS`, or starts with one of `AnalysisConfig::extra_blocked_prefixes`. Panic
+/// functions are kept when `AnalysisConfig::include_panic_sites` is set, and `std::` functions
+/// when `AnalysisConfig::include_stdlib_for_inference` is set.
+fn is_blocked(name: &str, config: &AnalysisConfig) -> bool {
+    if config.include_panic_sites && is_panic_function(name) {
+        return false;
+    }
+    let is_stdlib = config.include_stdlib_for_inference && name.starts_with("std::");
+
+    (!is_stdlib && BLOCKED_STRINGS.iter().any(|s| name.contains(s)))
+        || config
+            .extra_blocked_prefixes
+            .iter()
+            .any(|p| name.starts_with(p.as_str()))
+}
+
+/// Extracts the demangled `(caller, callee)` pairs of a parsed module, dropping any which are
+/// blocked, see `is_blocked`.
+fn module_calls(module: &Module, config: &AnalysisConfig) -> Vec<(String, String)> {
+    let analysis = ModuleAnalysis::new(module);
+    let graph = analysis.call_graph();
+
+    graph
+        .inner()
+        .all_edges()
+        .map(|(src_raw, dst_raw, ())| {
+            (
+                format!("{:#}", demangle(src_raw)),
+                format!("{:#}", demangle(dst_raw)),
+            )
+        })
+        .filter(|(src, dst)| !is_blocked(src, config) && !is_blocked(dst, config))
+        .collect()
+}
+
+/// Returns true if a call target is anything other than a direct reference to a named function,
+/// such as a function pointer held in a register.
+fn is_indirect_target(target: &Operand) -> bool {
+    match target {
+        Operand::ConstantOperand(c) => !matches!(c.as_ref(), Constant::GlobalReference { .. }),
+        _ => true,
+    }
+}
+
+/// Returns the demangled name of the containing function for every indirect call site in
+/// `module`, once per call site.
+fn module_indirect_calls(module: &Module) -> Vec<String> {
+    let mut callers = Vec::new();
+
+    for function in &module.functions {
+        for block in &function.basic_blocks {
+            let targets = block
+                .instrs
+                .iter()
+                .filter_map(|i| match i {
+                    Instruction::Call(call) => call.function.as_ref().right(),
+                    _ => None,
+                })
+                .chain(match &block.term {
+                    Terminator::Invoke(invoke) => invoke.function.as_ref().right(),
+                    _ => None,
+                });
+
+            let name = format!("{:#}", demangle(&function.name));
+            callers.extend(
+                targets
+                    .filter(|t| is_indirect_target(t))
+                    .map(|_| name.clone()),
+            );
+        }
+    }
+
+    callers
+}
+
+/// Options controlling how calls are extracted from bytecode.
+#[derive(Debug, Clone)]
+pub struct AnalysisConfig {
+    /// Collapse repeated `(caller, callee)` pairs returned by `extract_calls`. When `false`, every
+    /// observed call is returned, so duplicates can be counted with `count_calls`.
+    pub dedup_edges: bool,
+    /// Demangled names matching any of these are treated as test code by
+    /// `CallGraph::remove_test_functions_matching`.
+    pub test_function_patterns: Vec<Regex>,
+    /// Function name prefixes dropped in addition to `BLOCKED_STRINGS`, e.g. from
+    /// `PainterConfig::extra_blocked_prefixes`.
+    pub extra_blocked_prefixes: Vec<String>,
+    /// Keep calls to the standard library panic functions, see `graph::is_panic_function`, which
+    /// are otherwise dropped with the rest of `core` and `std`. Required for
+    /// `CallGraph::detect_panic_paths_from`.
+    pub include_panic_sites: bool,
+    /// Callees treated as returning a `Result`, see `patterns::detect_missing_error_handling`.
+    pub fallible_functions: Vec<Regex>,
+    /// Callees treated as handling an error, see `patterns::detect_missing_error_handling`.
+    pub error_handling_functions: Vec<Regex>,
+    /// Keep calls to `std::` functions, which are otherwise dropped. Required for
+    /// `patterns::infer_crate_categories`.
+    pub include_stdlib_for_inference: bool,
+}
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            dedup_edges: true,
+            test_function_patterns: vec![Regex::new(r"test|bench_|proptest_").unwrap()],
+            extra_blocked_prefixes: Vec::new(),
+            include_panic_sites: false,
+            fallible_functions: vec![
+                Regex::new(r"Result").unwrap(),
+                Regex::new(r"^std::fs::").unwrap(),
+                Regex::new(r"::(open|create|from_str|parse|try_from|try_into)$").unwrap(),
+            ],
+            error_handling_functions: vec![
+                Regex::new(r"::(unwrap(_or(_else|_default)?)?|expect|map_err)$").unwrap(),
+                Regex::new(r"::(ok(_or(_else)?)?|is_ok|is_err)$").unwrap(),
+                Regex::new(r"::(branch|from_residual)$").unwrap(),
+            ],
+            include_stdlib_for_inference: false,
+        }
+    }
+}
+impl AnalysisConfig {
+    /// The default config with `dedup_edges` off, for exports which write out every callsite.
+    #[must_use]
+    pub fn keep_duplicates() -> Self {
+        Self {
+            dedup_edges: false,
+            ..Self::default()
+        }
+    }
+}
+
+/// Returns true if `address` refers to a global variable, directly or through a constant
+/// `getelementptr` into one.
+fn is_global_address(address: &Operand) -> bool {
+    fn is_global(c: &Constant) -> bool {
+        match c {
+            Constant::GlobalReference { .. } => true,
+            Constant::GetElementPtr(gep) => is_global(&gep.address),
+            _ => false,
+        }
+    }
+
+    match address {
+        Operand::ConstantOperand(c) => is_global(c),
+        _ => false,
+    }
+}
+
+/// Returns the demangled names of all functions in `module` containing a `store` to a global.
+fn module_global_mutators(module: &Module) -> HashSet<String> {
+    module
+        .functions
+        .iter()
+        .filter(|f| {
+            f.basic_blocks
+                .iter()
+                .flat_map(|b| &b.instrs)
+                .any(|i| match i {
+                    Instruction::Store(store) => is_global_address(&store.address),
+                    _ => false,
+                })
+        })
+        .map(|f| format!("{:#}", demangle(&f.name)))
+        .collect()
+}
+
+/// Returns the LLVM types of the parameters of every function defined or declared in `module`,
+/// keyed by demangled name, e.g. `["ptr", "i64"]`.
+#[must_use]
+pub fn extract_function_argument_types(module: &Module) -> HashMap<String, Vec<String>> {
+    let types = |parameters: &[Parameter]| -> Vec<String> {
+        parameters.iter().map(|p| p.ty.to_string()).collect()
+    };
+
+    module
+        .functions
+        .iter()
+        .map(|f| (format!("{:#}", demangle(&f.name)), types(&f.parameters)))
+        .chain(
+            module
+                .func_declarations
+                .iter()
+                .map(|f| (format!("{:#}", demangle(&f.name)), types(&f.parameters))),
+        )
+        .collect()
+}
+
+/// A global constant holding function pointers, such as a trait object vtable, see
+/// `detect_dispatch_tables`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DispatchTable {
+    /// Demangled name of the global.
+    pub name: String,
+    /// Demangled names of the functions it points to, in table order.
+    pub functions: Vec<String>,
+}
+
+/// Finds the global constants of `module` which are arrays or structs (as rustc emits vtables)
+/// of pointers referencing at least one function defined or declared in the module. Only the
+/// function entries of each table are returned.
+#[must_use]
+pub fn detect_dispatch_tables(module: &Module) -> Vec<DispatchTable> {
+    let functions: HashSet<&str> = module
+        .functions
+        .iter()
+        .map(|f| f.name.as_str())
+        .chain(module.func_declarations.iter().map(|f| f.name.as_str()))
+        .collect();
+
+    module
+        .global_vars
+        .iter()
+        .filter(|g| g.is_constant)
+        .filter_map(|g| {
+            let entries = match g.initializer.as_deref()? {
+                Constant::Array { elements, .. } => elements,
+                Constant::Struct { values, .. } => values,
+                _ => return None,
+            };
+            let table: Vec<String> = entries
+                .iter()
+                .filter_map(|entry| match entry.as_ref() {
+                    Constant::GlobalReference {
+                        name: Name::Name(name),
+                        ..
+                    } if functions.contains(name.as_str()) => Some(format!("{:#}", demangle(name))),
+                    _ => None,
+                })
+                .collect();
+            if table.is_empty() {
+                return None;
+            }
+
+            let name = match &g.name {
+                Name::Name(name) => format!("{:#}", demangle(name)),
+                Name::Number(n) => n.to_string(),
+            };
+            Some(DispatchTable {
+                name,
+                functions: table,
+            })
+        })
+        .collect()
+}
+
+/// A function taking function pointers, and the functions observed being passed to it, see
+/// `detect_callback_registrations`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CallbackRegistration {
+    /// Demangled name of the function the callbacks are passed to.
+    pub registrar: String,
+    /// Zero based index of the argument holding the callback.
+    pub callback_arg_position: usize,
+    /// Demangled names of every function passed in that argument, sorted.
+    pub observed_callbacks: Vec<String>,
+}
+
+/// Finds calls in `module` passing a function of the module as an argument, such as a callback or
+/// thread entry point, to a function in `graph`. Callbacks are only ever invoked indirectly, so
+/// these are calls a plain call graph cannot see. One registration is returned per registrar and
+/// argument position, sorted by both.
+#[must_use]
+pub fn detect_callback_registrations(
+    module: &Module,
+    graph: &CallGraph,
+) -> Vec<CallbackRegistration> {
+    let functions: HashSet<&str> = module
+        .functions
+        .iter()
+        .map(|f| f.name.as_str())
+        .chain(module.func_declarations.iter().map(|f| f.name.as_str()))
+        .collect();
+
+    let mut registrations: BTreeMap<(String, usize), BTreeSet<String>> = BTreeMap::new();
+    let calls = module
+        .functions
+        .iter()
+        .flat_map(|f| &f.basic_blocks)
+        .flat_map(|b| &b.instrs)
+        .filter_map(|i| match i {
+            Instruction::Call(call) => Some(call),
+            _ => None,
+        });
+    for call in calls {
+        let Some(registrar) = called_function_name(call).filter(|r| graph.contains(r)) else {
+            continue;
+        };
+        for (position, (arg, _)) in call.arguments.iter().enumerate() {
+            let Operand::ConstantOperand(c) = arg else {
+                continue;
+            };
+            if let Constant::GlobalReference {
+                name: Name::Name(name),
+                ..
+            } = c.as_ref()
+            {
+                if functions.contains(name.as_str()) {
+                    registrations
+                        .entry((registrar.clone(), position))
+                        .or_default()
+                        .insert(format!("{:#}", demangle(name)));
+                }
+            }
+        }
+    }
+
+    registrations
+        .into_iter()
+        .map(
+            |((registrar, callback_arg_position), callbacks)| CallbackRegistration {
+                registrar,
+                callback_arg_position,
+                observed_callbacks: callbacks.into_iter().collect(),
+            },
+        )
+        .collect()
+}
+
+/// Returns the demangled names of the global constructors of `module`, the functions listed in
+/// the `@llvm.global_ctors` array, in priority order.
+#[must_use]
+pub fn detect_initialization_functions(module: &Module) -> Vec<String> {
+    let Some(Constant::Array { elements, .. }) = module
+        .global_vars
+        .iter()
+        .find(|g| g.name == Name::from("llvm.global_ctors"))
+        .and_then(|g| g.initializer.as_deref())
+    else {
+        return Vec::new();
+    };
+
+    // Each entry is a `{ i32 priority, ptr function, ptr data }` struct.
+    let mut ctors: Vec<(u64, String)> = elements
+        .iter()
+        .filter_map(|entry| match entry.as_ref() {
+            Constant::Struct { values, .. } => {
+                let priority = match values.first().map(AsRef::as_ref) {
+                    Some(Constant::Int { value, .. }) => *value,
+                    _ => u64::MAX,
+                };
+                match values.get(1).map(AsRef::as_ref) {
+                    Some(Constant::GlobalReference {
+                        name: Name::Name(name),
+                        ..
+                    }) => Some((priority, format!("{:#}", demangle(name)))),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect();
+    ctors.sort_by_key(|(priority, _)| *priority);
+
+    ctors.into_iter().map(|(_, name)| name).collect()
+}
+
 /// Extract all function calls/invocations within a bytecode file. Returns a `Vec<(String,String)>`
-/// of (caller, callee) demangled function names.
+/// of (caller, callee) demangled function names, deduplicated if `AnalysisConfig::dedup_edges`
+/// is set.
 ///
 /// # Panics
 /// This function will panic if iterating the `Roots::bytecode_root` fails.
@@ -18,36 +412,1233 @@ const BLOCKED_STRINGS: &[&str] = &["llvm.", "__rust", "rt::", "std::", "core::",
 /// # Errors
 /// TODO: Failure cases currently panic and should be moved to errors.
 #[allow(clippy::unnecessary_wraps)]
-pub fn extract_calls<P: AsRef<Path>>(crate_bc_dir: P) -> Result<Vec<(String, String)>, Error> {
+pub fn extract_calls<P: AsRef<Path>>(
+    crate_bc_dir: P,
+    config: &AnalysisConfig,
+) -> Result<Vec<(String, String)>, Error> {
     let mut calls = Vec::<(String, String)>::new();
 
-    for bc_entry in std::fs::read_dir(crate_bc_dir.as_ref())
-        .unwrap()
-        .filter_map(Result::ok)
-        .filter(|e| e.path().extension().is_some() && e.path().extension().unwrap() == "bc")
-    {
-        let bc_path = bc_entry.path();
-
+    for bc_path in bc_files(crate_bc_dir.as_ref()) {
         let module = Module::from_bc_path(&bc_path)
             .map_err(Error::LLVMError)
             .unwrap();
+
+        calls.extend(module_calls(&module, config));
+    }
+
+    if config.dedup_edges {
+        calls = merge_and_deduplicate_calls(calls);
+    }
+
+    Ok(calls)
+}
+
+/// Finds all call sites in the bytecode file at `bc_path` whose target is not a named function,
+/// i.e. calls through function pointers or vtables. Returns the demangled name of the containing
+/// function for each such call site.
+///
+/// # Errors
+/// Returns `Error::LLVMError` if the bytecode fails to parse.
+pub fn detect_indirect_calls(bc_path: &Path) -> Result<Vec<String>, Error> {
+    let module = Module::from_bc_path(bc_path).map_err(Error::LLVMError)?;
+    Ok(module_indirect_calls(&module))
+}
+
+/// Scans the bytecode at `bc_path` for `store` instructions writing to global variables, such as
+/// `static mut` items, and returns the sorted demangled names of the functions of `graph`
+/// performing them.
+///
+/// # Errors
+/// Returns `Error::LLVMError` if the bytecode fails to parse.
+pub fn detect_global_state_mutations(
+    graph: &CallGraph,
+    bc_path: &Path,
+) -> Result<Vec<String>, Error> {
+    let module = Module::from_bc_path(bc_path).map_err(Error::LLVMError)?;
+
+    let mut mutators: Vec<_> = module_global_mutators(&module)
+        .into_iter()
+        .filter(|f| graph.contains(f))
+        .collect();
+    mutators.sort();
+
+    Ok(mutators)
+}
+
+/// A pointer used by a `load` or `store` after being passed to a drop or deallocation call
+/// earlier in the same basic block, see `detect_use_after_free_patterns`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UseAfterFreePattern {
+    /// Demangled name of the containing function.
+    pub function: String,
+    /// Index of the drop call among the instructions of the function.
+    pub drop_site: u32,
+    /// Index of the `load` or `store` among the instructions of the function.
+    pub use_site: u32,
+}
+
+/// Returns the demangled name of the function called directly by `call`, if any.
+fn called_function_name(call: &Call) -> Option<String> {
+    match call.function.as_ref().right()? {
+        Operand::ConstantOperand(c) => match c.as_ref() {
+            Constant::GlobalReference {
+                name: Name::Name(name),
+                ..
+            } => Some(format!("{:#}", demangle(name))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns true if `callee` frees or drops the value behind its pointer argument.
+fn is_drop_function(callee: &str) -> bool {
+    callee == "__rust_dealloc"
+        || callee == "free"
+        || callee.starts_with("core::ptr::drop_in_place")
+        || callee.ends_with(">::drop")
+}
+
+/// Finds functions of `graph` in the bytecode at `bc_path` which pass a pointer to a drop or
+/// deallocation function and then `load` from or `store` to that same pointer later in the same
+/// basic block. This is a heuristic: it only tracks the exact pointer operand, misses uses in
+/// other blocks and flags pointers which are legitimately reinitialized.
+///
+/// # Errors
+/// Returns `Error::LLVMError` if the bytecode fails to parse.
+pub fn detect_use_after_free_patterns(
+    graph: &CallGraph,
+    bc_path: &Path,
+) -> Result<Vec<UseAfterFreePattern>, Error> {
+    let module = Module::from_bc_path(bc_path).map_err(Error::LLVMError)?;
+    let mut patterns = Vec::new();
+
+    for function in &module.functions {
+        let name = format!("{:#}", demangle(&function.name));
+        if !graph.contains(&name) {
+            continue;
+        }
+
+        let mut site = 0u32;
+        for block in &function.basic_blocks {
+            let mut dropped: Vec<(&Operand, u32)> = Vec::new();
+            for instr in &block.instrs {
+                match instr {
+                    Instruction::Call(call)
+                        if called_function_name(call).is_some_and(|c| is_drop_function(&c)) =>
+                    {
+                        dropped.extend(call.arguments.iter().map(|(arg, _)| (arg, site)));
+                    }
+                    Instruction::Load(Load { address, .. })
+                    | Instruction::Store(Store { address, .. }) => {
+                        if let Some((_, drop_site)) = dropped.iter().find(|(p, _)| *p == address) {
+                            patterns.push(UseAfterFreePattern {
+                                function: name.clone(),
+                                drop_site: *drop_site,
+                                use_site: site,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+                site += 1;
+            }
+            // The terminator counts as an instruction too.
+            site += 1;
+        }
+    }
+
+    Ok(patterns)
+}
+
+/// A call to a heap allocation function, see `detect_allocation_sites`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AllocationSite {
+    /// Demangled name of the function making the allocation.
+    pub function_name: String,
+    /// Source line of the call, from debug info.
+    pub call_site_line: Option<u32>,
+    /// Number of bytes requested, when it is a constant.
+    pub size_operand: Option<u64>,
+}
+
+/// Allocation functions, along with the index of their size argument(s). Sizes given as several
+/// arguments, such as the count and element size of `calloc`, are multiplied.
+const ALLOCATION_FUNCTIONS: &[(&str, &[usize])] = &[
+    ("__rust_alloc", &[0]),
+    ("__rust_alloc_zeroed", &[0]),
+    ("__rust_realloc", &[3]),
+    ("__rdl_alloc", &[0]),
+    ("__rdl_alloc_zeroed", &[0]),
+    ("__rdl_realloc", &[3]),
+    ("alloc::alloc::alloc", &[0]),
+    ("alloc::alloc::alloc_zeroed", &[0]),
+    ("alloc::alloc::realloc", &[2]),
+    ("alloc::alloc::exchange_malloc", &[0]),
+    ("malloc", &[0]),
+    ("calloc", &[0, 1]),
+    ("realloc", &[1]),
+];
+
+/// Returns every call to one of `ALLOCATION_FUNCTIONS` in `module`.
+fn module_allocation_sites(module: &Module) -> Vec<AllocationSite> {
+    let constant = |arg: Option<&(Operand, _)>| match arg {
+        Some((Operand::ConstantOperand(c), _)) => match c.as_ref() {
+            Constant::Int { value, .. } => Some(*value),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let mut sites = Vec::new();
+    for function in &module.functions {
+        let name = format!("{:#}", demangle(&function.name));
+        let calls = function
+            .basic_blocks
+            .iter()
+            .flat_map(|b| &b.instrs)
+            .filter_map(|i| match i {
+                Instruction::Call(call) => Some(call),
+                _ => None,
+            });
+
+        for call in calls {
+            let Some(callee) = called_function_name(call) else {
+                continue;
+            };
+            let Some((_, size_args)) = ALLOCATION_FUNCTIONS.iter().find(|(f, _)| *f == callee)
+            else {
+                continue;
+            };
+
+            sites.push(AllocationSite {
+                function_name: name.clone(),
+                call_site_line: call.debugloc.as_ref().map(|d| d.line),
+                size_operand: size_args
+                    .iter()
+                    .map(|&i| constant(call.arguments.get(i)))
+                    .try_fold(1u64, |size, arg| size.checked_mul(arg?)),
+            });
+        }
+    }
+
+    sites
+}
+
+/// Finds every call to a heap allocation function, `__rust_alloc`, `malloc` and friends along
+/// with their thin `alloc::alloc` wrappers, in the bytecode at `bc_path`.
+///
+/// # Errors
+/// Returns `Error::LLVMError` if the bytecode fails to parse.
+pub fn detect_allocation_sites(bc_path: &Path) -> Result<Vec<AllocationSite>, Error> {
+    let module = Module::from_bc_path(bc_path).map_err(Error::LLVMError)?;
+    Ok(module_allocation_sites(&module))
+}
+
+/// Decodes a constant byte array, or a struct wrapping one as rustc emits string literals, as a
+/// string. Returns `None` for any other constant or for bytes which are not valid UTF-8.
+fn constant_string(c: &Constant) -> Option<String> {
+    match c {
+        Constant::Struct { values, .. } if values.len() == 1 => constant_string(&values[0]),
+        Constant::Array { elements, .. } => {
+            let bytes = elements
+                .iter()
+                .map(|e| match e.as_ref() {
+                    Constant::Int { bits: 8, value } => u8::try_from(*value).ok(),
+                    _ => None,
+                })
+                .collect::<Option<Vec<u8>>>()?;
+            let s = String::from_utf8(bytes).ok()?;
+            Some(s.trim_end_matches('\0').to_owned())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the string literals passed to each callee in the bytecode at `bc_path`, keyed by the
+/// demangled callee name, in the order they appear. An argument counts as a string literal when
+/// it points, directly or through a constant `getelementptr`, to a global constant byte array.
+/// A file which fails to parse yields an empty map and a warning.
+#[must_use]
+pub fn extract_constant_string_arguments(bc_path: &Path) -> HashMap<String, Vec<String>> {
+    fn referenced_global(c: &Constant) -> Option<&Name> {
+        match c {
+            Constant::GlobalReference { name, .. } => Some(name),
+            Constant::GetElementPtr(gep) => referenced_global(&gep.address),
+            _ => None,
+        }
+    }
+
+    let module = match Module::from_bc_path(bc_path) {
+        Ok(module) => module,
+        Err(e) => {
+            log::warn!("Failed to parse {}: {}", bc_path.display(), e);
+            return HashMap::new();
+        }
+    };
+    let strings: HashMap<&Name, String> = module
+        .global_vars
+        .iter()
+        .filter(|g| g.is_constant)
+        .filter_map(|g| Some((&g.name, constant_string(g.initializer.as_deref()?)?)))
+        .collect();
+
+    let mut arguments: HashMap<String, Vec<String>> = HashMap::new();
+    let calls = module
+        .functions
+        .iter()
+        .flat_map(|f| &f.basic_blocks)
+        .flat_map(|b| &b.instrs)
+        .filter_map(|i| match i {
+            Instruction::Call(call) => Some(call),
+            _ => None,
+        });
+    for call in calls {
+        let Some(callee) = called_function_name(call) else {
+            continue;
+        };
+        for (arg, _) in &call.arguments {
+            let Operand::ConstantOperand(c) = arg else {
+                continue;
+            };
+            if let Some(s) = referenced_global(c).and_then(|name| strings.get(name)) {
+                arguments.entry(callee.clone()).or_default().push(s.clone());
+            }
+        }
+    }
+
+    arguments
+}
+
+/// Deduplicates the raw `(caller, callee)` pairs returned from `extract_calls`, returning the
+/// unique pairs sorted lexicographically.
+#[must_use]
+pub fn merge_and_deduplicate_calls(mut calls: Vec<(String, String)>) -> Vec<(String, String)> {
+    calls.sort_unstable();
+    calls.dedup();
+    calls
+}
+
+/// Counts how many times each `(caller, callee)` pair occurs in `calls`. This is the same count
+/// `CallGraph` records as the edge call count.
+#[must_use]
+pub fn count_calls(calls: &[(String, String)]) -> HashMap<(String, String), usize> {
+    let mut counts = HashMap::new();
+    for call in calls {
+        *counts.entry(call.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Magic bytes accepted by `validate_bc_file`: raw bitcode (`BC` 0xC0DE) and the bitcode wrapper
+/// header 0x0B17C0DE in either byte order.
+const BC_MAGICS: &[[u8; 4]] = &[
+    [0x42, 0x43, 0xC0, 0xDE],
+    [0xDE, 0xC0, 0x17, 0x0B],
+    [0x0B, 0x17, 0xC0, 0xDE],
+];
+
+/// Checks that `path` starts with LLVM bitcode magic bytes, so misnamed files such as ELF objects
+/// are rejected before being handed to the LLVM parser.
+///
+/// # Errors
+/// Returns `Error::IoError` if the file cannot be read, or `Error::LLVMError` if it is not a
+/// bitcode file.
+pub fn validate_bc_file(path: &Path) -> Result<(), Error> {
+    use std::io::Read;
+
+    let mut magic = [0u8; 4];
+    let read = std::fs::File::open(path)?.read(&mut magic)?;
+    if read < magic.len() || !BC_MAGICS.contains(&magic) {
+        return Err(Error::LLVMError(format!(
+            "not a valid LLVM bitcode file: {}",
+            path.display()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the demangled name and debug info location of every function defined in `module`
+/// which has one.
+fn module_locations(module: &Module) -> Vec<(String, SourceLocation)> {
+    module
+        .functions
+        .iter()
+        .filter_map(|function| {
+            let debugloc = function.debugloc.as_ref()?;
+            Some((
+                format!("{:#}", demangle(&function.name)),
+                SourceLocation {
+                    file: debugloc.filename.clone(),
+                    line: debugloc.line,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// The `(demangled, mangled)` names of every function defined or declared in the module.
+fn module_symbols(module: &Module) -> Vec<(String, String)> {
+    module
+        .functions
+        .iter()
+        .map(|f| f.name.as_str())
+        .chain(module.func_declarations.iter().map(|f| f.name.as_str()))
+        .map(|name| (format!("{:#}", demangle(name)), name.to_owned()))
+        .collect()
+}
+
+/// Parses the bytecode file at `bc_path` and adds its calls and function flags to `graph`, see
+/// `extract_calls_from_files`.
+fn add_bc_file(
+    graph: &mut CallGraph,
+    bc_path: &Path,
+    config: &AnalysisConfig,
+) -> Result<(), Error> {
+    validate_bc_file(bc_path)?;
+    let module = Module::from_bc_path(bc_path).map_err(Error::LLVMError)?;
+    add_module(graph, &module, config);
+
+    Ok(())
+}
+
+/// Adds the calls and node attributes of a parsed module to `graph`, see `add_bc_file`.
+fn add_module(graph: &mut CallGraph, module: &Module, config: &AnalysisConfig) {
+    for (caller, callee) in module_calls(module, config) {
+        graph.add_edge(caller, callee);
+    }
+
+    for caller in module_indirect_calls(module) {
+        if let Some(node) = graph.node_mut(&caller) {
+            node.has_indirect_calls = true;
+        }
+    }
+    for mutator in module_global_mutators(module) {
+        if let Some(node) = graph.node_mut(&mutator) {
+            node.mutates_globals = true;
+        }
+    }
+    for (name, mangled) in module_symbols(module) {
+        if let Some(node) = graph.node_mut(&name) {
+            node.mangled = Some(mangled);
+        }
+    }
+    for (name, location) in module_locations(module) {
+        if let Some(node) = graph.node_mut(&name) {
+            node.location = Some(location);
+        }
+    }
+    for (name, parameter_types) in extract_function_argument_types(module) {
+        if let Some(node) = graph.node_mut(&name) {
+            node.parameter_types = parameter_types;
+        }
+    }
+    for site in module_allocation_sites(module) {
+        if let Some(node) = graph.node_mut(&site.function_name) {
+            node.allocation_site_count += 1;
+        }
+    }
+    for table in detect_dispatch_tables(module) {
+        for function in table.functions {
+            if let Some(node) = graph.node_mut(&function) {
+                node.in_vtable = true;
+            }
+        }
+    }
+    for ctor in detect_initialization_functions(module) {
+        if !is_blocked(&ctor, config) {
+            graph.add_node(ctor).is_global_ctor = true;
+        }
+    }
+}
+
+/// Parses the bytecode at `bc_path` with `parse`, normally `Module::from_bc_path`, on its own
+/// thread, giving up after `timeout`. A parse which times out is left to finish in the background
+/// and its result discarded.
+fn parse_with_timeout<T, F>(bc_path: &Path, timeout: Duration, parse: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce(PathBuf) -> Result<T, String> + Send + 'static,
+{
+    let start = Instant::now();
+    let (tx, rx) = mpsc::channel();
+    let path = bc_path.to_owned();
+    std::thread::spawn(move || {
+        // The receiver is gone if the parse timed out, so a failed send is expected.
+        let _ = tx.send(parse(path));
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(module) => module.map_err(Error::LLVMError),
+        Err(RecvTimeoutError::Timeout) => Err(Error::AnalysisTimeout {
+            path: bc_path.to_owned(),
+            elapsed: start.elapsed(),
+        }),
+        Err(RecvTimeoutError::Disconnected) => Err(Error::LLVMError(format!(
+            "parser thread for {} panicked",
+            bc_path.display()
+        ))),
+    }
+}
+
+/// Builds a `CallGraph` from the given bytecode files. Calls are extracted as in `extract_calls`;
+/// the graph records both the unique edges and their raw call counts. Functions containing
+/// indirect call sites are flagged `FunctionNode::has_indirect_calls`, and those writing to
+/// globals `FunctionNode::mutates_globals`. `FunctionNode::location` is set from debug info.
+///
+/// # Errors
+/// Returns `Error::LLVMError` if any file is not bitcode or fails to parse, see
+/// `validate_bc_file`.
+pub fn extract_calls_from_files<P: AsRef<Path>>(
+    files: &[P],
+    config: &AnalysisConfig,
+) -> Result<CallGraph, Error> {
+    let mut graph = CallGraph::new();
+
+    for bc_path in files {
+        add_bc_file(&mut graph, bc_path.as_ref(), config)?;
+    }
+
+    Ok(graph)
+}
+
+/// Builds a `CallGraph` from all bytecode files within `bc_dir` as `extract_call_graph` does, but
+/// parses each file on a separate thread with a `timeout`. Files which take longer are skipped
+/// with a warning rather than blocking the rest of the analysis.
+///
+/// # Panics
+/// This function will panic if iterating the directory fails.
+/// # Errors
+/// Returns `Error::LLVMError` if any bytecode file fails to parse.
+pub fn extract_calls_with_timeout(
+    bc_dir: &Path,
+    timeout: Duration,
+    config: &AnalysisConfig,
+) -> Result<CallGraph, Error> {
+    let mut graph = CallGraph::new();
+
+    for bc_path in bc_files(bc_dir) {
+        validate_bc_file(&bc_path)?;
+        match parse_with_timeout(&bc_path, timeout, Module::from_bc_path) {
+            Ok(module) => add_module(&mut graph, &module, config),
+            Err(e @ Error::AnalysisTimeout { .. }) => log::warn!("Skipping: {e}"),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Time spent in, and edges produced by, each stage of call extraction, see
+/// `measure_analysis_performance`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AnalysisMetrics {
+    /// Parsing bytecode with `Module::from_bc_path`.
+    pub parse_time_ms: u64,
+    /// Building the LLVM call graph and demangling its edges.
+    pub graph_build_time_ms: u64,
+    /// Dropping blocked functions and, with `AnalysisConfig::dedup_edges`, duplicate edges.
+    pub filter_time_ms: u64,
+    pub total_edges_before_filter: usize,
+    pub total_edges_after_filter: usize,
+    pub bc_files_analyzed: usize,
+}
+
+/// Runs the stages of `extract_calls` over the bytecode in `bc_dir`, timing each one. Files which
+/// fail to parse are skipped with a warning and not counted as analyzed.
+///
+/// # Panics
+/// This function will panic if iterating the directory fails.
+#[must_use]
+pub fn measure_analysis_performance(bc_dir: &Path, config: &AnalysisConfig) -> AnalysisMetrics {
+    fn millis(elapsed: Duration) -> u64 {
+        u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+    }
+
+    let mut metrics = AnalysisMetrics::default();
+    let mut calls = Vec::new();
+
+    for bc_path in bc_files(bc_dir) {
+        let start = Instant::now();
+        let module = match Module::from_bc_path(&bc_path) {
+            Ok(module) => module,
+            Err(e) => {
+                log::warn!("Skipping {}: {}", bc_path.display(), e);
+                continue;
+            }
+        };
+        metrics.parse_time_ms += millis(start.elapsed());
+        metrics.bc_files_analyzed += 1;
+
+        let start = Instant::now();
         let analysis = ModuleAnalysis::new(&module);
+        let raw: Vec<(String, String)> = analysis
+            .call_graph()
+            .inner()
+            .all_edges()
+            .map(|(src_raw, dst_raw, ())| {
+                (
+                    format!("{:#}", demangle(src_raw)),
+                    format!("{:#}", demangle(dst_raw)),
+                )
+            })
+            .collect();
+        metrics.graph_build_time_ms += millis(start.elapsed());
+        metrics.total_edges_before_filter += raw.len();
 
-        let graph = analysis.call_graph();
-        graph.inner().all_edges().for_each(|(src_raw, dst_raw, _)| {
-            let src = format!("{:#}", demangle(src_raw));
-            let dst = format!("{:#}", demangle(dst_raw));
+        let start = Instant::now();
+        calls.extend(
+            raw.into_iter()
+                .filter(|(src, dst)| !is_blocked(src, config) && !is_blocked(dst, config)),
+        );
+        metrics.filter_time_ms += millis(start.elapsed());
+    }
 
-            if !BLOCKED_STRINGS
+    let start = Instant::now();
+    if config.dedup_edges {
+        calls = merge_and_deduplicate_calls(calls);
+    }
+    metrics.filter_time_ms += millis(start.elapsed());
+    metrics.total_edges_after_filter = calls.len();
+
+    metrics
+}
+
+/// Builds a single `CallGraph` from every `.bc` file anywhere under `root`, such as a whole
+/// `Roots::bytecodes_root` of `<name>-<version>` directories, as `extract_calls_from_files`
+/// does. Each directory is logged as it is processed, along with the overall progress.
+///
+/// # Errors
+/// Returns `Error::LLVMError` if any file is not bitcode or fails to parse.
+pub fn extract_calls_from_directory_recursive(
+    root: &Path,
+    config: &AnalysisConfig,
+) -> Result<CallGraph, Error> {
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(walkdir::DirEntry::into_path)
+        .filter(|p| p.extension().is_some_and(|e| e == "bc"))
+        .collect();
+    files.sort();
+
+    let mut graph = CallGraph::new();
+    let mut current_dir = None;
+    for (i, bc_path) in files.iter().enumerate() {
+        if current_dir != bc_path.parent() {
+            current_dir = bc_path.parent();
+            log::info!(
+                "Processing: {} ({}/{} files)",
+                current_dir.unwrap_or(root).display(),
+                i,
+                files.len()
+            );
+        }
+        add_bc_file(&mut graph, bc_path, config)?;
+    }
+    log::info!("Processed {} files under {}", files.len(), root.display());
+
+    Ok(graph)
+}
+
+/// Callee name used by `extract_calls_from_wasm` for `call_indirect` instructions.
+const WASM_INDIRECT_CALLEE: &str = "<indirect>";
+
+/// Builds a `CallGraph` from the code section of a WebAssembly module, without requiring LLVM IR.
+/// Every `call` becomes an edge between the demangled names of the two functions, and every
+/// `call_indirect` an edge to `"<indirect>"`, filtered and deduplicated as in
+/// `extract_calls_from_object_file`. Functions are named from the `name` custom section, falling
+/// back to export and import names, and finally to `func[<index>]`.
+///
+/// # Errors
+/// Returns `Error::IoError` if the file cannot be read, or `Error::WasmError` if it cannot be
+/// parsed as a WebAssembly module.
+pub fn extract_calls_from_wasm(
+    wasm_path: &Path,
+    config: &AnalysisConfig,
+) -> Result<CallGraph, Error> {
+    use wasmparser::{ExternalKind, KnownCustom, Name, Operator, Parser, Payload, TypeRef};
+
+    let data = std::fs::read(wasm_path)?;
+    let mut names: HashMap<u32, String> = HashMap::new();
+    let mut fallback_names: HashMap<u32, String> = HashMap::new();
+    let mut imported_functions = 0u32;
+    let mut bodies = Vec::new();
+
+    for payload in Parser::new(0).parse_all(&data) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    if let TypeRef::Func(_) = import.ty {
+                        fallback_names.insert(imported_functions, import.name.to_owned());
+                        imported_functions += 1;
+                    }
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    if export.kind == ExternalKind::Func {
+                        fallback_names.insert(export.index, export.name.to_owned());
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => bodies.push(body),
+            Payload::CustomSection(reader) => {
+                if let KnownCustom::Name(reader) = reader.as_known() {
+                    for subsection in reader {
+                        if let Name::Function(map) = subsection? {
+                            for naming in map {
+                                let naming = naming?;
+                                names.insert(naming.index, naming.name.to_owned());
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let name_of = |index: u32| -> String {
+        names
+            .get(&index)
+            .or_else(|| fallback_names.get(&index))
+            .map_or_else(
+                || format!("func[{index}]"),
+                |name| format!("{:#}", demangle(name)),
+            )
+    };
+
+    let mut graph = CallGraph::new();
+    for (index, body) in (imported_functions..).zip(bodies) {
+        let caller = name_of(index);
+        if is_blocked(&caller, config) {
+            continue;
+        }
+
+        let mut operators = body.get_operators_reader()?;
+        while !operators.eof() {
+            let callee = match operators.read()? {
+                Operator::Call { function_index } => name_of(function_index),
+                Operator::CallIndirect { .. } => WASM_INDIRECT_CALLEE.to_owned(),
+                _ => continue,
+            };
+            if is_blocked(&callee, config)
+                || (config.dedup_edges && graph.call_count(&caller, &callee).is_some())
+            {
+                continue;
+            }
+
+            graph.add_edge(&caller, callee);
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Demangles a symbol from a PDB file, which may be a Rust symbol or an MSVC C++ one (starting
+/// with `?`). Unrecognized symbols are returned unchanged.
+fn demangle_pdb_symbol(symbol: &str) -> String {
+    if symbol.starts_with('?') {
+        msvc_demangler::demangle(symbol, msvc_demangler::DemangleFlags::NAME_ONLY)
+            .unwrap_or_else(|_| symbol.to_owned())
+    } else {
+        format!("{:#}", demangle(symbol))
+    }
+}
+
+/// Returns the symbol an incremental linking thunk such as `ILT+1234(?foo@@YAXXZ)`, or an import
+/// thunk such as `__imp_foo`, jumps to.
+fn pdb_thunk_target(thunk: &str) -> Option<&str> {
+    if let Some(rest) = thunk.strip_prefix("ILT+") {
+        let (_, target) = rest.split_once('(')?;
+        return target.strip_suffix(')');
+    }
+    thunk.strip_prefix("__imp_")
+}
+
+/// Builds a best-effort `CallGraph` from a Windows PDB file, for when only symbols are shipped.
+/// Functions come from the public and global symbols and the procedures of each module; PDB files
+/// record no calls, so the only edges are from linker thunks to the functions they jump to. Every
+/// node is flagged `FunctionNode::is_approximate`. Names are demangled as Rust symbols, falling
+/// back to MSVC C++ demangling.
+///
+/// # Errors
+/// Returns `Error::IoError` if the file cannot be read, or `Error::PdbError` if it cannot be
+/// parsed as a PDB file.
+pub fn extract_calls_from_pdb_file(pdb_path: &Path) -> Result<CallGraph, Error> {
+    use pdb::{FallibleIterator, SymbolData, SymbolIter, PDB};
+
+    fn add_symbols(graph: &mut CallGraph, mut symbols: SymbolIter<'_>) -> Result<(), Error> {
+        while let Some(symbol) = symbols.next()? {
+            match symbol.parse() {
+                Ok(SymbolData::Public(public)) if public.function => {
+                    graph.add_node(demangle_pdb_symbol(&public.name.to_string()));
+                }
+                Ok(SymbolData::Procedure(procedure)) => {
+                    graph.add_node(demangle_pdb_symbol(&procedure.name.to_string()));
+                }
+                Ok(SymbolData::Thunk(thunk)) => {
+                    let name = thunk.name.to_string();
+                    if let Some(target) = pdb_thunk_target(&name) {
+                        graph.add_edge(demangle_pdb_symbol(&name), demangle_pdb_symbol(target));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut pdb = PDB::open(std::fs::File::open(pdb_path)?)?;
+    let mut graph = CallGraph::new();
+
+    let globals = pdb.global_symbols()?;
+    add_symbols(&mut graph, globals.iter())?;
+
+    let debug_information = pdb.debug_information()?;
+    let mut modules = debug_information.modules()?;
+    while let Some(module) = modules.next()? {
+        if let Some(info) = pdb.module_info(&module)? {
+            add_symbols(&mut graph, info.symbols()?)?;
+        }
+    }
+
+    for node in graph.nodes_mut() {
+        node.is_approximate = true;
+    }
+
+    Ok(graph)
+}
+
+/// Builds a `CallGraph` from the relocations of an ELF object file, as a fallback when no bitcode
+/// is available. Every `R_X86_64_PLT32` and `R_X86_64_PC32` relocation in a text section becomes
+/// an edge from the function containing it to the demangled target symbol, filtered as in
+/// `extract_calls`. With `AnalysisConfig::dedup_edges` each edge is counted once, otherwise once
+/// per relocation.
+///
+/// # Errors
+/// Returns `Error::IoError` if the file cannot be read, or `Error::ObjectError` if it cannot be
+/// parsed as an object file.
+pub fn extract_calls_from_object_file(
+    obj_path: &Path,
+    config: &AnalysisConfig,
+) -> Result<CallGraph, Error> {
+    use object::{
+        elf, Object, ObjectSection, ObjectSymbol, RelocationFlags, RelocationTarget, SectionKind,
+        SymbolKind,
+    };
+
+    let data = std::fs::read(obj_path)?;
+    let file = object::File::parse(&*data)?;
+    let mut graph = CallGraph::new();
+
+    for section in file.sections().filter(|s| s.kind() == SectionKind::Text) {
+        let mut functions: Vec<_> = file
+            .symbols()
+            .filter(|s| s.section_index() == Some(section.index()) && s.kind() == SymbolKind::Text)
+            .collect();
+        functions.sort_by_key(ObjectSymbol::address);
+
+        for (offset, relocation) in section.relocations() {
+            if !matches!(
+                relocation.flags(),
+                RelocationFlags::Elf {
+                    r_type: elf::R_X86_64_PLT32 | elf::R_X86_64_PC32
+                }
+            ) {
+                continue;
+            }
+            let RelocationTarget::Symbol(target) = relocation.target() else {
+                continue;
+            };
+
+            let address = section.address() + offset;
+            let caller = functions
                 .iter()
-                .any(|s| src.contains(*s) || dst.contains(*s))
+                .rev()
+                .find(|f| f.address() <= address)
+                .filter(|f| f.size() == 0 || address < f.address() + f.size())
+                .and_then(|f| f.name().ok());
+            let callee = file
+                .symbol_by_index(target)
+                .ok()
+                .and_then(|s| s.name().ok());
+            let (Some(caller), Some(callee)) = (caller, callee) else {
+                continue;
+            };
+
+            let caller = format!("{:#}", demangle(caller));
+            let callee = format!("{:#}", demangle(callee));
+            if is_blocked(&caller, config)
+                || is_blocked(&callee, config)
+                || (config.dedup_edges && graph.call_count(&caller, &callee).is_some())
             {
-                calls.push((src, dst));
+                continue;
             }
-        });
+
+            graph.add_edge(caller, callee);
+        }
     }
 
-    Ok(calls)
+    Ok(graph)
+}
+
+/// Reads the `Cargo.lock` of `workspace_root` and returns the locked version of every dependency,
+/// keyed by package name, for use as the `version` of `analyze_crate_from_registry`. Workspace
+/// members and other packages without a source are skipped. Where several versions of a package
+/// are locked, the highest is returned.
+///
+/// # Errors
+/// Returns `Error::LockfileError` if the lockfile is missing or fails to parse.
+pub fn resolve_cargo_lock_versions(
+    workspace_root: &Path,
+) -> Result<HashMap<String, String>, Error> {
+    let lockfile = cargo_lock::Lockfile::load(workspace_root.join("Cargo.lock"))?;
+
+    let mut versions: HashMap<String, cargo_lock::Version> = HashMap::new();
+    for package in lockfile.packages.into_iter().filter(|p| p.source.is_some()) {
+        match versions.get(package.name.as_str()) {
+            Some(version) if *version >= package.version => {}
+            _ => {
+                versions.insert(package.name.as_str().to_owned(), package.version);
+            }
+        }
+    }
+
+    Ok(versions
+        .into_iter()
+        .map(|(name, version)| (name, version.to_string()))
+        .collect())
+}
+
+/// Number of crate boundaries a call chain must cross to be reported by
+/// `detect_long_dependency_chains`.
+const LONG_CHAIN_CRATE_HOPS: usize = 4;
+
+/// A call chain crossing many crate boundaries, see `detect_long_dependency_chains`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DependencyCallChain {
+    /// Demangled names of the functions along the chain, from its entry point.
+    pub function_path: Vec<String>,
+    /// Number of calls in the chain between two different workspace packages or dependencies.
+    pub crate_hops: usize,
+}
+
+/// Finds the call chains of `graph` which cross `LONG_CHAIN_CRATE_HOPS` boundaries between the
+/// crates named in `packages`; calls into or out of other crates are not hops. Chains are searched
+/// breadth-first from every entry point over pairs of function and hop count, so a chain is found
+/// even when a shorter path to the same function crosses fewer crates. Each chain is the one with
+/// the fewest calls and is reported once, ending at the function where it reaches the hop limit.
+/// Crates calling back into each other can make a chain pass through a function more than once.
+#[must_use]
+pub fn find_long_dependency_chains<S: std::hash::BuildHasher>(
+    graph: &CallGraph,
+    packages: &HashSet<String, S>,
+) -> Vec<DependencyCallChain> {
+    let is_hop = |caller: &str, callee: &str| {
+        let (from, to) = (crate_name(caller), crate_name(callee));
+        from != to && packages.contains(from) && packages.contains(to)
+    };
+
+    let mut reported = HashSet::new();
+    let mut chains = Vec::new();
+    for root in graph.roots() {
+        let mut parents: HashMap<(&str, usize), (&str, usize)> = HashMap::new();
+        let mut visited = HashSet::from([(root, 0)]);
+        let mut queue = std::collections::VecDeque::from([(root, 0)]);
+        while let Some(state) = queue.pop_front() {
+            let (current, current_hops) = state;
+            if current_hops >= LONG_CHAIN_CRATE_HOPS {
+                if reported.insert(current) {
+                    let mut function_path = vec![current.to_owned()];
+                    let mut node = state;
+                    while let Some(&parent) = parents.get(&node) {
+                        function_path.push(parent.0.to_owned());
+                        node = parent;
+                    }
+                    function_path.reverse();
+                    chains.push(DependencyCallChain {
+                        function_path,
+                        crate_hops: current_hops,
+                    });
+                }
+                continue;
+            }
+
+            for callee in graph.callees(current) {
+                let next = (callee, current_hops + usize::from(is_hop(current, callee)));
+                if visited.insert(next) {
+                    parents.insert(next, state);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    chains
+}
+
+/// Finds call chains in the workspace at `workspace_root` which cross `LONG_CHAIN_CRATE_HOPS`
+/// crate boundaries, a sign of layering problems such as low level crates calling back up into
+/// high level ones. The call graph is built from the bytecode already in the workspace's target
+/// directory, e.g. from `compile::compile_bitcode`, and only calls between packages listed by
+/// `cargo metadata` count as hops. Returns the function path of each chain found by
+/// `find_long_dependency_chains`.
+///
+/// # Errors
+/// Returns `Error::MetadataError` if `cargo metadata` fails, `Error::IoError` if it cannot be
+/// run, and `Error::LLVMError` if any bytecode fails to parse.
+pub fn detect_long_dependency_chains(workspace_root: &Path) -> Result<Vec<Vec<String>>, Error> {
+    let output = std::process::Command::new("cargo")
+        .args(["metadata", "--format-version", "1"])
+        .current_dir(workspace_root)
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::MetadataError(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&output.stdout).map_err(|e| Error::MetadataError(e.to_string()))?;
+
+    let packages: HashSet<String> = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| p["name"].as_str())
+        .map(|name| name.replace('-', "_"))
+        .collect();
+    let target_dir = metadata["target_directory"]
+        .as_str()
+        .map_or_else(|| workspace_root.join("target"), PathBuf::from);
+
+    let graph = extract_calls_from_directory_recursive(&target_dir, &AnalysisConfig::default())?;
+    Ok(find_long_dependency_chains(&graph, &packages)
+        .into_iter()
+        .map(|chain| chain.function_path)
+        .collect())
+}
+
+/// Runs the complete pipeline for a single crates.io crate within `work_dir`: the `.crate` file is
+/// downloaded into `work_dir/crates`, extracted into `work_dir/sources`, compiled with
+/// `compile::compile_crate` into `work_dir/bytecode` and analyzed with `extract_call_graph`. The
+/// extracted sources are removed afterwards, unless compilation fails with
+/// `CompileOptions::preserve_on_failure` set.
+///
+/// # Errors
+/// Returns `Error::CrateFsError` if downloading or extracting the crate fails,
+/// `Error::CompileError` if compilation fails, and `Error::LLVMError` if analysis fails.
+pub fn analyze_crate_from_registry(
+    name: &str,
+    version: &str,
+    work_dir: &Path,
+    opts: &CompileOptions,
+) -> Result<CallGraph, Error> {
+    let entry = CrateEntry::new(format!("{name}-{version}"))?;
+    let crates_dir = work_dir.join("crates");
+    let sources_dir = work_dir.join("sources");
+    let bc_root = work_dir.join("bytecode");
+    for dir in [&crates_dir, &sources_dir, &bc_root] {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    log::info!("Downloading: {}", entry.full_name());
+    crate_fs::download_crate(&entry, &crates_dir)?;
+
+    log::info!("Extracting: {}", entry.full_name());
+    let mut cache = CrateCache::new(&entry, &crates_dir, &sources_dir)?;
+
+    if let Err(e) = compile::compile_crate(name, version, cache.path(), &bc_root, opts) {
+        if opts.preserve_on_failure {
+            log::warn!("Preserving {} after failure", cache.path().display());
+            cache.preserve();
+        }
+        return Err(e.into());
+    }
+
+    log::info!("Analyzing: {}", entry.full_name());
+    extract_call_graph(bc_root.join(entry.full_name()), &AnalysisConfig::default())
+}
+
+/// Builds a `CallGraph` from all bytecode files within a crates bytecode directory, see
+/// `extract_calls_from_files`.
+///
+/// # Panics
+/// This function will panic if iterating the directory fails.
+/// # Errors
+/// Returns `Error::LLVMError` if any bytecode file fails to parse.
+pub fn extract_call_graph<P: AsRef<Path>>(
+    crate_bc_dir: P,
+    config: &AnalysisConfig,
+) -> Result<CallGraph, Error> {
+    extract_calls_from_files(&bc_files(crate_bc_dir.as_ref()), config)
+}
+
+/// Returns all nodes of `graph` not reachable from any of `entry_points`, excluding any matching
+/// `BLOCKED_STRINGS`. These are candidates for dead code; entry points missing from the graph are
+/// ignored.
+#[must_use]
+pub fn detect_dead_code_candidates<'a>(
+    graph: &'a CallGraph,
+    entry_points: &[&str],
+) -> Vec<&'a FunctionNode> {
+    let reachable: HashSet<&str> = entry_points
+        .iter()
+        .flat_map(|e| graph.reachable_from(e))
+        .collect();
+
+    graph
+        .nodes()
+        .filter(|n| !reachable.contains(n.demangled.as_str()))
+        .filter(|n| !BLOCKED_STRINGS.iter().any(|s| n.demangled.contains(s)))
+        .collect()
+}
+
+/// Enumerates every simple path (no repeated nodes) from `from` to `to` with at most `max_depth`
+/// calls, using depth-first search with backtracking. Paths include both endpoints and are
+/// returned in lexicographic order. Empty if no such path exists or either node is not in the
+/// graph.
+#[must_use]
+pub fn enumerate_all_simple_paths(
+    graph: &CallGraph,
+    from: &str,
+    to: &str,
+    max_depth: usize,
+) -> Vec<Vec<String>> {
+    fn visit<'a>(
+        graph: &'a CallGraph,
+        to: &str,
+        max_depth: usize,
+        path: &mut Vec<&'a str>,
+        on_path: &mut HashSet<&'a str>,
+        paths: &mut Vec<Vec<String>>,
+    ) {
+        let current = path[path.len() - 1];
+        if current == to {
+            paths.push(path.iter().map(|n| (*n).to_owned()).collect());
+            return;
+        }
+        if path.len() > max_depth {
+            return;
+        }
+
+        for callee in graph.callees(current) {
+            if on_path.insert(callee) {
+                path.push(callee);
+                visit(graph, to, max_depth, path, on_path, paths);
+                path.pop();
+                on_path.remove(callee);
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    let (Some(start), true) = (graph.node(from), graph.contains(to)) else {
+        return paths;
+    };
+
+    let start = start.demangled.as_str();
+    visit(
+        graph,
+        to,
+        max_depth,
+        &mut vec![start],
+        &mut HashSet::from([start]),
+        &mut paths,
+    );
+
+    paths
+}
+
+/// Maximum number of calls in the paths reported by `detect_transitive_unsafe_exposure`, bounding
+/// the otherwise exponential path enumeration.
+const UNSAFE_EXPOSURE_MAX_DEPTH: usize = 16;
+
+/// Finds every simple path of at most `UNSAFE_EXPOSURE_MAX_DEPTH` calls from `public_entry` to a
+/// function marked `FunctionNode::is_unsafe`, through which a safe public function exposes the
+/// unsafety of its callees. Paths include both endpoints and are sorted; `public_entry` being
+/// unsafe itself is not reported. Relies on `CallGraph::mark_unsafe_functions` having been run.
+#[must_use]
+pub fn detect_transitive_unsafe_exposure(
+    graph: &CallGraph,
+    public_entry: &str,
+) -> Vec<Vec<String>> {
+    let mut paths: Vec<Vec<String>> = graph
+        .reachable_from(public_entry)
+        .into_iter()
+        .filter(|n| *n != public_entry && graph.node(n).is_some_and(|n| n.is_unsafe))
+        .flat_map(|target| {
+            enumerate_all_simple_paths(graph, public_entry, target, UNSAFE_EXPOSURE_MAX_DEPTH)
+        })
+        .collect();
+    paths.sort();
+
+    paths
+}
+
+/// Runs `detect_transitive_unsafe_exposure` from every function marked by
+/// `CallGraph::mark_public_api_surface`, returning the paths of each public function exposing
+/// unsafety.
+#[must_use]
+pub fn all_transitively_unsafe_public_apis(graph: &CallGraph) -> HashMap<String, Vec<Vec<String>>> {
+    graph
+        .public_api_surface()
+        .into_par_iter()
+        .map(|n| {
+            (
+                n.demangled.clone(),
+                detect_transitive_unsafe_exposure(graph, &n.demangled),
+            )
+        })
+        .filter(|(_, paths)| !paths.is_empty())
+        .collect()
+}
+
+/// Finds the linear spines of the graph with at least `min_length` functions: maximal paths
+/// `a -> b -> ... -> z` along which every function except `z` calls only the next one, and every
+/// function except `a` is called only by the previous one. Returned in order of their first
+/// function; cycles made up entirely of such links are not reported.
+#[must_use]
+pub fn detect_long_call_chains(graph: &CallGraph, min_length: usize) -> Vec<Vec<String>> {
+    // The single callee of `name`, if calling it is a link of a chain.
+    let next = |name: &str| {
+        let mut callees = graph.callees(name);
+        match (callees.next(), callees.next()) {
+            (Some(callee), None) if graph.in_degree(callee) == 1 => Some(callee),
+            _ => None,
+        }
+    };
+
+    let mut chains = Vec::new();
+    for start in graph.nodes().map(|n| n.demangled.as_str()) {
+        let continues_chain = graph.in_degree(start) == 1
+            && graph
+                .callers(start)
+                .next()
+                .is_some_and(|caller| next(caller) == Some(start));
+        if continues_chain || next(start).is_none() {
+            continue;
+        }
+
+        let mut chain = vec![start];
+        while let Some(callee) = next(chain[chain.len() - 1]) {
+            if chain.contains(&callee) {
+                break;
+            }
+            chain.push(callee);
+        }
+
+        if chain.len() >= min_length {
+            chains.push(chain.into_iter().map(str::to_owned).collect());
+        }
+    }
+
+    chains
 }
 
 /// Extracts all calls within a  single crates bytecode. Then, perform database insertions of each
@@ -60,18 +1651,18 @@ pub fn extract_calls<P: AsRef<Path>>(crate_bc_dir: P) -> Result<Vec<(String, Str
 /// Returns `painter::analysis::Error` on failure of database insertion.
 #[allow(clippy::needless_pass_by_value)]
 pub async fn export_crate_db<P: AsRef<Path>>(crate_bc_dir: P, db: Arc<Db>) -> Result<(), Error> {
-    let calls = extract_calls(&crate_bc_dir)?;
+    let calls = extract_calls(&crate_bc_dir, &AnalysisConfig::keep_duplicates())?;
     let crate_fullname = crate_bc_dir.as_ref().file_name().unwrap().to_str().unwrap();
 
     let (crate_name, crate_version) = crate_fullname.rsplit_once('-').unwrap();
 
     // If this crate/version has an invoke, assume its completed and bail
     if db.has_any_invoke(crate_name, crate_version).await? {
-        log::trace!("{}-{} Exists, skipping..", crate_name, crate_version);
+        log::trace!("{crate_name}-{crate_version} Exists, skipping..");
         return Ok(());
     }
 
-    log::trace!("Importing: {}", crate_name);
+    log::trace!("Importing: {crate_name}");
 
     for (caller, callee) in &calls {
         let dst_crate = callee.split_once("::").unwrap_or(("NONE", "")).0;
@@ -145,60 +1736,12 @@ impl CountUnsafeResult {
     }
 }
 
-pub(crate) async fn count_unsafe_crate_extract(
-    c: Crate,
-    roots: Roots,
-    db: Arc<Db>,
-) -> Result<(), Error> {
-    let compressed_root = &roots.compressed_root;
+async fn count_unsafe_crate(c: Crate, roots: Roots, db: Arc<Db>) -> Result<(), Error> {
     let sources_root = &roots.sources_root;
 
     for v in c.versions() {
         let crate_fullname = format!("{}-{}", v.name(), v.version());
-        let crate_path = compressed_root.join(format!("{}.crate", &crate_fullname));
-
-        // Lets work off the tgz for now, since we cant extract
-        // TODO: this needs to be unified to a file driver
-        if std::fs::metadata(&crate_path).is_ok() {
-            let extracted_path = sources_root.join(&crate_fullname);
-            let tar_gz = std::fs::File::open(&crate_path).unwrap();
-            let tar = flate2::read::GzDecoder::new(tar_gz);
-            let mut archive = tar::Archive::new(tar);
-            if archive.unpack(sources_root).is_ok() {
-                log::trace!("Extracted {}", &crate_fullname);
-
-                // Run our count
-                let output = std::process::Command::new("count-unsafe")
-                    .args([&extracted_path])
-                    .output()
-                    .unwrap();
-                if output.status.success() {
-                    let raw_json = std::str::from_utf8(&output.stdout).unwrap();
-                    log::trace!("{}", &raw_json);
-
-                    let unsafe_result: CountUnsafeResult = serde_json::from_str(raw_json).unwrap();
-                    if unsafe_result.has_unsafe() {
-                        log::debug!("{} unsafe", &crate_fullname);
-                        db.set_unsafe(v.name(), v.version(), &unsafe_result).await;
-                        //.unwrap();
-                    }
-
-                    // Finally delete
-                    //std::fs::remove_dir_all(extracted_path).unwrap();
-                    log::trace!("Deleted {}", &crate_fullname);
-                }
-            }
-        }
-    }
-    Ok(())
-}
-pub(crate) async fn count_unsafe_crate(c: Crate, roots: Roots, db: Arc<Db>) -> Result<(), Error> {
-    let compressed_root = &roots.compressed_root;
-    let sources_root = &roots.sources_root;
-
-    for v in c.versions() {
-        let crate_fullname = format!("{}-{}", v.name(), v.version());
-        let crate_path = sources_root.join(format!("{}", &crate_fullname));
+        let crate_path = sources_root.join(&crate_fullname);
 
         // Lets work off the tgz for now, since we cant extract
         // TODO: this needs to be unified to a file driver
@@ -210,13 +1753,12 @@ pub(crate) async fn count_unsafe_crate(c: Crate, roots: Roots, db: Arc<Db>) -> R
                 .unwrap();
             if output.status.success() {
                 let raw_json = std::str::from_utf8(&output.stdout).unwrap();
-                log::trace!("{}", &raw_json);
+                log::trace!("{raw_json}");
 
                 let unsafe_result: CountUnsafeResult = serde_json::from_str(raw_json).unwrap();
                 if unsafe_result.has_unsafe() {
-                    log::debug!("{} unsafe", &crate_fullname);
-                    db.set_unsafe(v.name(), v.version(), &unsafe_result).await;
-                    //.unwrap();
+                    log::debug!("{crate_fullname} unsafe");
+                    db.set_unsafe(v.name(), v.version(), &unsafe_result).await?;
                 }
             }
         }
@@ -224,7 +1766,15 @@ pub(crate) async fn count_unsafe_crate(c: Crate, roots: Roots, db: Arc<Db>) -> R
     Ok(())
 }
 
-pub(crate) async fn count_unsafe(roots: &Roots, db: Arc<Db>) -> Result<(), Error> {
+/// Runs `count-unsafe` over the extracted sources of every crate in the cargo index, storing the
+/// counts of crates containing unsafe code in `db`.
+///
+/// # Panics
+/// This function will panic if `count-unsafe` cannot be executed or its output cannot be parsed.
+///
+/// # Errors
+/// Returns `Error::IndexError` if the cargo index cannot be opened.
+pub async fn count_unsafe(roots: &Roots, db: Arc<Db>) -> Result<(), Error> {
     let index = crates_index::Index::new_cargo_default().map_err(crate::index::Error::from)?;
 
     let iter = index.crates().array_chunks::<128>();
@@ -240,28 +1790,40 @@ pub(crate) async fn count_unsafe(roots: &Roots, db: Arc<Db>) -> Result<(), Error
     Ok(())
 }
 
-#[allow(dead_code)]
-fn export_crate_csv<P: AsRef<Path>>(crate_bc_dir: P) -> Result<(), Error> {
-    let calls = extract_calls(&crate_bc_dir)?;
+/// Writes the calls of the crate in `crate_bc_dir` to `calls.csv` within it, one
+/// `crate,caller,callee` row per callsite.
+///
+/// # Panics
+/// Panics if the crate directory name is not valid UTF-8 or `calls.csv` cannot be written.
+/// # Errors
+/// Returns `painter::analysis::Error` if the bytecode cannot be analyzed.
+pub fn export_crate_csv<P: AsRef<Path>>(crate_bc_dir: P) -> Result<(), Error> {
+    let calls = extract_calls(&crate_bc_dir, &AnalysisConfig::keep_duplicates())?;
     let crate_fullname = crate_bc_dir.as_ref().file_name().unwrap().to_str().unwrap();
 
     {
         let mut file = std::fs::OpenOptions::new()
             .write(true)
             .create(true)
+            .truncate(true)
             .open(crate_bc_dir.as_ref().join("calls.csv"))
             .unwrap();
 
-        calls.iter().enumerate().for_each(|(_, (src, dst))| {
+        for (src, dst) in &calls {
             writeln!(file, "{crate_fullname},{src},{dst}").unwrap();
-        });
+        }
     }
 
     Ok(())
 }
 
-#[allow(dead_code)]
-fn export_all_csv<P: AsRef<Path>>(bc_root: P) -> Result<(), Error> {
+/// Iterate across all crates in the bytecode root in parallel, and call `export_crate_csv`
+///
+/// # Panics
+/// This function panics if exporting any crate fails.
+/// # Errors
+/// Returns `Error::IoError` if the bytecode root directory cannot be read.
+pub fn export_all_csv<P: AsRef<Path>>(bc_root: P) -> Result<(), Error> {
     let dirs: Vec<_> = std::fs::read_dir(&bc_root)?
         .filter_map(Result::ok)
         .filter(|e| e.path().is_dir())
@@ -273,3 +1835,156 @@ fn export_all_csv<P: AsRef<Path>>(bc_root: P) -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_parse_times_out() {
+        let bc = Path::new("slow.bc");
+        let timeout = Duration::from_millis(20);
+        let slow = |_| {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(())
+        };
+        match parse_with_timeout(bc, timeout, slow) {
+            Err(Error::AnalysisTimeout { path, elapsed }) => {
+                assert_eq!(path, bc);
+                assert!(elapsed >= timeout);
+            }
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+
+        assert!(parse_with_timeout(bc, timeout, |_| Ok(())).is_ok());
+        assert!(matches!(
+            parse_with_timeout::<(), _>(bc, timeout, |_| Err("bad".to_owned())),
+            Err(Error::LLVMError(e)) if e == "bad"
+        ));
+    }
+
+    #[test]
+    fn static_mut_writer_mutates_globals() {
+        let bc = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_data/global_mutation.bc");
+        let graph = extract_calls_from_files(&[&bc], &AnalysisConfig::default()).unwrap();
+        assert_eq!(graph.node_count(), 3);
+
+        assert_eq!(
+            detect_global_state_mutations(&graph, &bc).unwrap(),
+            ["globals::bump"]
+        );
+        assert!(graph.node("globals::bump").unwrap().mutates_globals);
+        assert!(!graph.node("globals::get").unwrap().mutates_globals);
+        assert!(!graph.node("globals::main").unwrap().mutates_globals);
+    }
+
+    #[test]
+    fn dead_code_candidates_are_unreachable_functions() {
+        let graph: CallGraph = [
+            ("app::main", "app::run"),
+            ("app::run", "app::helper"),
+            ("app::unused", "app::helper"),
+            ("app::unused", "core::fmt::write"),
+        ]
+        .into_iter()
+        .collect();
+
+        let dead: Vec<_> = detect_dead_code_candidates(&graph, &["app::main"])
+            .into_iter()
+            .map(|n| n.demangled.as_str())
+            .collect();
+        assert_eq!(dead, ["app::unused"]);
+    }
+
+    /// `a` calls `b` and `c`, which both call `d`.
+    fn diamond() -> CallGraph {
+        [("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn simple_paths_through_a_diamond() {
+        let graph = diamond();
+        assert_eq!(
+            enumerate_all_simple_paths(&graph, "a", "d", 8),
+            [["a", "b", "d"], ["a", "c", "d"]]
+        );
+        assert!(enumerate_all_simple_paths(&graph, "a", "d", 1).is_empty());
+        assert!(enumerate_all_simple_paths(&graph, "d", "a", 8).is_empty());
+    }
+
+    #[test]
+    fn long_call_chain_of_eight_functions() {
+        let chain: Vec<String> = (0..8).map(|i| format!("app::step{i}")).collect();
+        let mut graph: CallGraph = chain.windows(2).map(|w| (&w[0], &w[1])).collect();
+        graph.add_edge("app::main", &chain[0]);
+        graph.add_edge("app::main", "app::log");
+        graph.add_edge(&chain[7], "app::log");
+
+        assert_eq!(detect_long_call_chains(&graph, 8), [chain]);
+        assert!(detect_long_call_chains(&graph, 9).is_empty());
+    }
+
+    #[test]
+    fn dependency_chain_behind_a_shortcut() {
+        let graph: CallGraph = [
+            ("a::main", "e::sink"),
+            ("a::main", "b::f"),
+            ("b::f", "std::io::read"),
+            ("b::f", "c::f"),
+            ("c::f", "d::f"),
+            ("d::f", "e::sink"),
+        ]
+        .into_iter()
+        .collect();
+        let packages = ["a", "b", "c", "d", "e"].map(str::to_owned).into();
+
+        assert_eq!(
+            find_long_dependency_chains(&graph, &packages),
+            [DependencyCallChain {
+                function_path: vec![
+                    "a::main".to_owned(),
+                    "b::f".to_owned(),
+                    "c::f".to_owned(),
+                    "d::f".to_owned(),
+                    "e::sink".to_owned(),
+                ],
+                crate_hops: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn unsafe_exposure_three_calls_deep() {
+        let mut graph: CallGraph = [
+            ("api::open", "inner::a"),
+            ("api::open", "inner::log"),
+            ("inner::a", "inner::b"),
+            ("inner::b", "sys::raw"),
+            ("api::close", "inner::log"),
+        ]
+        .into_iter()
+        .collect();
+        graph.node_mut("sys::raw").unwrap().is_unsafe = true;
+        for name in ["api::open", "api::close"] {
+            graph.node_mut(name).unwrap().is_public_api = true;
+        }
+
+        let path = vec![
+            "api::open".to_owned(),
+            "inner::a".to_owned(),
+            "inner::b".to_owned(),
+            "sys::raw".to_owned(),
+        ];
+        assert_eq!(
+            detect_transitive_unsafe_exposure(&graph, "api::open"),
+            std::slice::from_ref(&path)
+        );
+        assert!(detect_transitive_unsafe_exposure(&graph, "api::close").is_empty());
+        assert_eq!(
+            all_transitively_unsafe_public_apis(&graph),
+            HashMap::from([("api::open".to_owned(), vec![path])])
+        );
+    }
+}
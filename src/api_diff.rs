@@ -0,0 +1,192 @@
+use crate::graph::{function_name, is_extern_c, module_path, CallGraph};
+use std::collections::BTreeSet;
+
+/// Minimum `similarity` between function names for a removed function to be reported as renamed.
+const RENAME_SIMILARITY: f64 = 0.6;
+
+/// How an entry point of the old graph changed in the new one.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum BreakKind {
+    /// No function resembling it is left in the new graph.
+    FunctionRemoved,
+    /// A new function with a similar name was added to the same module.
+    FunctionRenamed { new_name: String },
+    /// It is still present but became unsafe to call, see `FunctionNode::is_unsafe`.
+    NewUnsafeFunction,
+}
+
+/// A breaking change to one of the entry points of a crate, see `detect_version_bump_api_breaks`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ApiBreak {
+    pub kind: BreakKind,
+    pub function: String,
+}
+
+/// Normalized Levenshtein similarity between `a` and `b`, from 0 (nothing in common) to 1
+/// (identical).
+#[allow(clippy::cast_precision_loss)]
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    1.0 - row[b.len()] as f64 / a.len().max(b.len()) as f64
+}
+
+/// Compares the entry points (nodes without callers) of `old` against `new`, reporting each one
+/// missing from `new`. A missing function is classified as renamed when a function added in
+/// `new` lives in the same module and has a similar name, preferring the most similar; otherwise
+/// it is reported as removed. Entry points which are safe in `old` but unsafe in `new` are
+/// reported as well. Results are sorted by function name.
+#[must_use]
+pub fn detect_version_bump_api_breaks(old: &CallGraph, new: &CallGraph) -> Vec<ApiBreak> {
+    let added: Vec<&str> = new
+        .nodes()
+        .map(|n| n.demangled.as_str())
+        .filter(|n| !old.contains(n))
+        .collect();
+
+    let became_unsafe = old
+        .roots()
+        .filter(|name| !old.node(name).is_some_and(|n| n.is_unsafe))
+        .filter(|name| new.node(name).is_some_and(|n| n.is_unsafe))
+        .map(|name| ApiBreak {
+            kind: BreakKind::NewUnsafeFunction,
+            function: name.to_owned(),
+        });
+
+    let mut breaks: Vec<_> = old
+        .roots()
+        .filter(|name| !new.contains(name))
+        .map(|name| {
+            let renamed = added
+                .iter()
+                .filter(|candidate| module_path(candidate) == module_path(name))
+                .map(|candidate| {
+                    let score = similarity(function_name(name), function_name(candidate));
+                    (*candidate, score)
+                })
+                .filter(|(_, score)| *score >= RENAME_SIMILARITY)
+                .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(a.0)));
+
+            ApiBreak {
+                kind: match renamed {
+                    Some((new_name, _)) => BreakKind::FunctionRenamed {
+                        new_name: new_name.to_owned(),
+                    },
+                    None => BreakKind::FunctionRemoved,
+                },
+                function: name.to_owned(),
+            }
+        })
+        .chain(became_unsafe)
+        .collect();
+
+    breaks.sort_by(|a, b| a.function.cmp(&b.function));
+    breaks
+}
+
+/// Kind of version bump between two releases of a crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SemverBump {
+    Major,
+    Minor,
+    Patch,
+}
+
+/// A change not allowed by a `SemverBump`, see `detect_semver_violations`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ViolationKind {
+    /// A function marked `FunctionNode::is_public_api` is gone. Not allowed in minor and patch
+    /// releases.
+    RemovedPublicFunction,
+    /// A foreign function, one without a Rust path, is called for the first time. Not allowed in
+    /// minor and patch releases.
+    NewExternCDependency,
+    /// A function calls an unsafe function, see `FunctionNode::is_unsafe`, which it did not call
+    /// before. Not allowed in patch releases.
+    AddedUnsafeCallsite,
+}
+
+/// A single change of a release which its version bump does not allow.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SemverViolation {
+    pub kind: ViolationKind,
+    pub affected_function: String,
+}
+
+/// Checks the changes from `old` to `new` against what `bump` allows. Public functions are those
+/// flagged by `CallGraph::mark_public_api_surface` and unsafe functions those flagged by
+/// `CallGraph::mark_unsafe_functions`, so both graphs should be marked first. Major bumps allow
+/// any change. Results are sorted by kind and then function name.
+#[must_use]
+pub fn detect_semver_violations(
+    old_graph: &CallGraph,
+    new_graph: &CallGraph,
+    bump: SemverBump,
+) -> Vec<SemverViolation> {
+    if bump == SemverBump::Major {
+        return Vec::new();
+    }
+
+    let extern_callees = |graph: &CallGraph| -> BTreeSet<String> {
+        graph
+            .edges()
+            .map(|(_, callee, _)| callee)
+            .filter(|callee| is_extern_c(callee))
+            .map(ToOwned::to_owned)
+            .collect()
+    };
+
+    let removed = old_graph
+        .nodes()
+        .filter(|n| n.is_public_api)
+        .filter(|n| {
+            !new_graph
+                .node(&n.demangled)
+                .is_some_and(|new| new.is_public_api)
+        })
+        .map(|n| SemverViolation {
+            kind: ViolationKind::RemovedPublicFunction,
+            affected_function: n.demangled.clone(),
+        });
+    let old_externs = extern_callees(old_graph);
+    let new_externs = extern_callees(new_graph)
+        .into_iter()
+        .filter(|callee| !old_externs.contains(callee))
+        .map(|callee| SemverViolation {
+            kind: ViolationKind::NewExternCDependency,
+            affected_function: callee,
+        });
+
+    // Callers are reported once, however many unsafe callsites they gained.
+    let unsafe_callers: BTreeSet<String> = if bump == SemverBump::Patch {
+        new_graph
+            .edges()
+            .filter(|(_, callee, _)| new_graph.node(callee).is_some_and(|n| n.is_unsafe))
+            .filter(|(caller, callee, _)| old_graph.call_count(caller, callee).is_none())
+            .map(|(caller, _, _)| caller.to_owned())
+            .collect()
+    } else {
+        BTreeSet::new()
+    };
+    let unsafe_callsites = unsafe_callers.into_iter().map(|caller| SemverViolation {
+        kind: ViolationKind::AddedUnsafeCallsite,
+        affected_function: caller,
+    });
+
+    removed.chain(new_externs).chain(unsafe_callsites).collect()
+}
@@ -1,8 +1,9 @@
 #![allow(clippy::module_name_repetitions)]
 
-use crate::crate_fs::{CrateCache, CrateEntry, CrateFs};
+use crate::crate_fs::CrateFs;
 use crates_index::{Crate, Index};
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
@@ -14,8 +15,8 @@ pub enum Error {
     #[error("IO Error: {0}")]
     IoError(#[from] std::io::Error),
     ///
-    #[error("Crate compilation failed")]
-    CompileFailed(String),
+    #[error("Compilation failed: {0:?}")]
+    CompileFailed(CompileFailure),
     ///
     #[error("Clean stage failed")]
     CleanFailure(std::process::Output),
@@ -28,6 +29,398 @@ pub enum Error {
     ///
     #[error("Indexing Error: {0}")]
     CrateFsError(#[from] crate::crate_fs::Error),
+    ///
+    #[error("No Cargo.lock found in: {0}")]
+    LockfileNotFound(PathBuf),
+    ///
+    #[error("Invalid batch manifest: {0}")]
+    ManifestError(String),
+    ///
+    #[error("Required tool unavailable: {0}")]
+    ToolNotFound(String),
+    ///
+    #[error("Tests failed: {} passed, {} failed", .0.passed, .0.failed)]
+    TestFailed(TestResults),
+}
+
+/// Outcome of the `cargo test` run in `compile_crate_and_run_tests`.
+#[derive(Debug, Clone, Default)]
+pub struct TestResults {
+    pub passed: u32,
+    pub failed: u32,
+    pub ignored: u32,
+    /// Combined stdout and stderr of cargo.
+    pub output: String,
+}
+impl TestResults {
+    /// Builds `TestResults` from the output of `cargo test`, summing the counts of every
+    /// `test result:` line. Returns `None` if there are none, i.e. no test binary ran.
+    ///
+    /// # Panics
+    /// This function should not panic; the summary regex is a constant.
+    #[must_use]
+    pub fn from_output(output: &std::process::Output) -> Option<Self> {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        let re = regex::Regex::new(r"test result: \w+\. (\d+) passed; (\d+) failed; (\d+) ignored")
+            .unwrap();
+        let mut results = Self {
+            output: format!("{stdout}{stderr}"),
+            ..Self::default()
+        };
+        let mut found = false;
+        for captures in re.captures_iter(&stdout) {
+            found = true;
+            results.passed += captures[1].parse::<u32>().unwrap_or(0);
+            results.failed += captures[2].parse::<u32>().unwrap_or(0);
+            results.ignored += captures[3].parse::<u32>().unwrap_or(0);
+        }
+
+        found.then_some(results)
+    }
+}
+
+/// Output of a failed compilation, along with the rustc error codes (e.g. `E0308`) found in it.
+#[derive(Debug, Clone)]
+pub struct CompileFailure {
+    pub stdout: String,
+    pub stderr: String,
+    /// Error codes in the order they were reported, without duplicates.
+    pub error_codes: Vec<String>,
+}
+impl CompileFailure {
+    /// Builds a `CompileFailure` from the output of a cargo invocation, parsing error codes from
+    /// `error[ENNNN]:` diagnostics in stderr.
+    ///
+    /// # Panics
+    /// This function should not panic; the error code regex is a constant.
+    #[must_use]
+    pub fn from_output(output: &std::process::Output) -> Self {
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+        let re = regex::Regex::new(r"error\[(E\d{4})\]:").unwrap();
+        let mut error_codes = Vec::<String>::new();
+        for code in re.captures_iter(&stderr).map(|c| c[1].to_owned()) {
+            if !error_codes.contains(&code) {
+                error_codes.push(code);
+            }
+        }
+
+        Self {
+            stdout,
+            stderr,
+            error_codes,
+        }
+    }
+
+    /// The first error code reported by the compiler.
+    #[must_use]
+    pub fn primary_error(&self) -> Option<&str> {
+        self.error_codes.first().map(String::as_str)
+    }
+}
+
+/// The outcome of compiling one crate of a batch manifest, see `batch_compile_from_manifest`.
+pub type BatchResult = Result<Vec<PathBuf>, Error>;
+
+/// Options shared by the compile variants which build a crate outside of `compile_all`.
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CompileOptions {
+    /// Toolchain passed to cargo as `+<toolchain>`.
+    pub toolchain: String,
+    /// Extra rustc flags, passed through `CARGO_ENCODED_RUSTFLAGS` and merged after any flags
+    /// inherited from the environment.
+    pub rustflags: Vec<String>,
+    /// Build with the incremental debug profile instead of `--release`, and keep the `target`
+    /// directory between runs rather than cleaning it.
+    pub incremental: bool,
+    /// Pass `--locked`, requiring the dependency versions recorded in `Cargo.lock`. Compiling
+    /// fails with `Error::LockfileNotFound` if the crate has no lockfile.
+    pub locked: bool,
+    /// Pass `--frozen` instead of `--locked`, which additionally prevents network access.
+    pub frozen: bool,
+    /// Pass `--offline` and set `CARGO_NET_OFFLINE`, so cargo never touches the network and
+    /// only uses dependencies already available locally.
+    pub offline: bool,
+    /// Source of a `main` harness calling into the crate. When set it is written to
+    /// `examples/_painter_harness.rs` for the duration of the build and compiled with
+    /// `--example _painter_harness` instead of `--lib`, giving the graph custom entry points.
+    pub entrypoint_harness: Option<String>,
+    /// Leave the crate's sources and `target` directory in place when compilation fails, so the
+    /// failure can be debugged.
+    pub preserve_on_failure: bool,
+    /// Crate features to enable, passed to cargo as `--features`.
+    pub features: Vec<String>,
+    /// Target triple to build for, passed to cargo as `--target`. Defaults to the host.
+    pub target_triple: Option<String>,
+}
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            toolchain: "1.67".to_owned(),
+            rustflags: Vec::new(),
+            incremental: false,
+            locked: false,
+            frozen: false,
+            offline: false,
+            entrypoint_harness: None,
+            preserve_on_failure: false,
+            features: Vec::new(),
+            target_triple: None,
+        }
+    }
+}
+
+/// Separator cargo uses between flags in `CARGO_ENCODED_RUSTFLAGS`.
+const ENCODED_RUSTFLAGS_SEPARATOR: char = '\x1f';
+
+/// Encodes `CompileOptions::rustflags` for `CARGO_ENCODED_RUSTFLAGS`, merged after the flags of the
+/// calling process. Cargo ignores `RUSTFLAGS` once the encoded variable is set, so inherited
+/// `RUSTFLAGS` are carried over when no encoded flags are inherited. Returns `None` when there are
+/// no extra flags, leaving the inherited environment untouched.
+fn encoded_rustflags(opts: &CompileOptions) -> Option<String> {
+    merge_rustflags(
+        std::env::var("CARGO_ENCODED_RUSTFLAGS").ok().as_deref(),
+        std::env::var("RUSTFLAGS").ok().as_deref(),
+        &opts.rustflags,
+    )
+}
+
+/// Merges `extra` flags after the inherited `encoded` or, failing that, `plain` flags, as
+/// described by `encoded_rustflags`.
+fn merge_rustflags(encoded: Option<&str>, plain: Option<&str>, extra: &[String]) -> Option<String> {
+    if extra.is_empty() {
+        return None;
+    }
+
+    let inherited: Vec<&str> = match encoded {
+        Some(encoded) if !encoded.is_empty() => {
+            encoded.split(ENCODED_RUSTFLAGS_SEPARATOR).collect()
+        }
+        _ => plain
+            .map(|flags| flags.split_whitespace().collect())
+            .unwrap_or_default(),
+    };
+
+    Some(
+        inherited
+            .into_iter()
+            .chain(extra.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(&ENCODED_RUSTFLAGS_SEPARATOR.to_string()),
+    )
+}
+
+/// Name of the example target written for `CompileOptions::entrypoint_harness`.
+const HARNESS_NAME: &str = "_painter_harness";
+
+/// Cargo target arguments for `opts`: the entry point harness if one is set, otherwise `--lib`.
+fn target_args(opts: &CompileOptions) -> &'static [&'static str] {
+    if opts.entrypoint_harness.is_some() {
+        &["--example", HARNESS_NAME]
+    } else {
+        &["--lib"]
+    }
+}
+
+/// Writes `CompileOptions::entrypoint_harness` into a crate for the lifetime of the guard, removing
+/// it again (and the `examples` directory, if it was created for it) when dropped.
+struct HarnessGuard {
+    path: PathBuf,
+    created_dir: Option<PathBuf>,
+}
+impl HarnessGuard {
+    fn write(src_path: &Path, opts: &CompileOptions) -> Result<Option<Self>, Error> {
+        let Some(harness) = &opts.entrypoint_harness else {
+            return Ok(None);
+        };
+
+        let dir = src_path.join("examples");
+        let created_dir = if dir.exists() {
+            None
+        } else {
+            std::fs::create_dir(&dir)?;
+            Some(dir.clone())
+        };
+        let guard = Self {
+            path: dir.join(format!("{HARNESS_NAME}.rs")),
+            created_dir,
+        };
+        std::fs::write(&guard.path, harness)?;
+
+        Ok(Some(guard))
+    }
+}
+impl Drop for HarnessGuard {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            log::warn!("Failed to remove harness {}: {}", self.path.display(), e);
+        }
+        if let Some(dir) = &self.created_dir {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+}
+
+/// Cargo arguments selecting the features and target triple from `opts`.
+fn build_args(opts: &CompileOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    if !opts.features.is_empty() {
+        args.extend(["--features".to_owned(), opts.features.join(",")]);
+    }
+    if let Some(target) = &opts.target_triple {
+        args.extend(["--target".to_owned(), target.clone()]);
+    }
+    args
+}
+
+/// Cargo profile arguments for `opts`: `--release`, unless building incrementally.
+fn profile_args(opts: &CompileOptions) -> &'static [&'static str] {
+    if opts.incremental {
+        &[]
+    } else {
+        &["--release"]
+    }
+}
+
+/// Creates a `cargo +<toolchain>` command with the environment described by `opts`.
+fn cargo_command(opts: &CompileOptions) -> std::process::Command {
+    let mut command = std::process::Command::new("cargo");
+    command.arg(format!("+{}", opts.toolchain));
+
+    if let Some(flags) = encoded_rustflags(opts) {
+        command.env("CARGO_ENCODED_RUSTFLAGS", flags);
+    }
+    if opts.incremental {
+        command.env("CARGO_INCREMENTAL", "1");
+    }
+    if opts.frozen {
+        command.arg("--frozen");
+    } else if opts.locked {
+        command.arg("--locked");
+    }
+    if opts.offline {
+        command.arg("--offline").env("CARGO_NET_OFFLINE", "true");
+    }
+
+    command
+}
+
+/// Fails with `Error::LockfileNotFound` if `opts` requires a lockfile and `src_path` has none.
+fn check_lockfile(src_path: &Path, opts: &CompileOptions) -> Result<(), Error> {
+    if (opts.locked || opts.frozen) && !src_path.join("Cargo.lock").exists() {
+        return Err(Error::LockfileNotFound(src_path.to_path_buf()));
+    }
+
+    Ok(())
+}
+
+/// Free disk space, in GB, below which `validate_compilation_environment` warns.
+const MIN_DISK_SPACE_GB: f64 = 10.0;
+
+/// Result of `validate_compilation_environment`.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct EnvironmentReport {
+    /// Output of `cargo --version`.
+    pub cargo_version: String,
+    /// Installed toolchains, as listed by `rustup toolchain list`.
+    pub rustup_toolchains: Vec<String>,
+    /// Free space on the file system of the current directory.
+    pub disk_space_gb: f64,
+    /// Problems which do not prevent compiling, but may cause it to fail.
+    pub warnings: Vec<String>,
+}
+
+/// Runs `program` with `args`, returning its trimmed stdout if it ran successfully.
+fn tool_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Free space in GB of the file system containing the current directory, according to `df`.
+#[allow(clippy::cast_precision_loss)]
+fn free_disk_space_gb() -> Option<f64> {
+    // The second line of `df -Pk` output is `<fs> <blocks> <used> <available> ..`, in KiB.
+    let output = tool_output("df", &["-Pk", "."])?;
+    let available: u64 = output
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()?;
+    Some(available as f64 / (1024.0 * 1024.0))
+}
+
+/// Checks that the tools `compile_crate` relies on are installed: `cargo` and `rustup` must run,
+/// while a missing `toolchain`, a missing `llvm-objdump` or less than `MIN_DISK_SPACE_GB` of free
+/// disk space are reported as warnings.
+///
+/// # Errors
+/// Returns `Error::ToolNotFound` if `cargo` or `rustup` cannot be run.
+pub fn validate_compilation_environment(toolchain: &str) -> Result<EnvironmentReport, Error> {
+    let cargo_version = tool_output("cargo", &["--version"])
+        .ok_or_else(|| Error::ToolNotFound("cargo".to_owned()))?;
+    let rustup_toolchains: Vec<String> = tool_output("rustup", &["toolchain", "list"])
+        .ok_or_else(|| Error::ToolNotFound("rustup".to_owned()))?
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(ToOwned::to_owned)
+        .collect();
+
+    let mut warnings = Vec::new();
+    if !rustup_toolchains.iter().any(|t| t.starts_with(toolchain)) {
+        warnings.push(format!(
+            "toolchain {toolchain} is not installed, run `rustup toolchain install {toolchain}`"
+        ));
+    }
+    if tool_output("llvm-objdump", &["--version"]).is_none() {
+        warnings.push("llvm-objdump not found, bytecode cannot be inspected".to_owned());
+    }
+    let disk_space_gb = free_disk_space_gb().unwrap_or_else(|| {
+        warnings.push("could not determine free disk space".to_owned());
+        0.0
+    });
+    if disk_space_gb > 0.0 && disk_space_gb < MIN_DISK_SPACE_GB {
+        warnings.push(format!("only {disk_space_gb:.1} GB of disk space free"));
+    }
+
+    Ok(EnvironmentReport {
+        cargo_version,
+        rustup_toolchains,
+        disk_space_gb,
+        warnings,
+    })
+}
+
+/// Sanitizers which can be enabled with `compile_with_sanitizer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanitizer {
+    Address,
+    Memory,
+    Thread,
+    Leak,
+}
+impl Sanitizer {
+    /// The name accepted by `-Z sanitizer=`.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Address => "address",
+            Self::Memory => "memory",
+            Self::Thread => "thread",
+            Self::Leak => "leak",
+        }
+    }
 }
 
 /// Executes a cargo clean within the crates sources directory. This is executed within the
@@ -48,7 +441,8 @@ pub fn clean(path: &Path) -> Result<(), Error> {
         .output()
         .unwrap();
 
-    std::fs::remove_dir_all(path.join("target"));
+    // A crate which never produced a target directory has nothing to remove.
+    let _ = std::fs::remove_dir_all(path.join("target"));
 
     if output.status.success() {
         Ok(())
@@ -57,6 +451,64 @@ pub fn clean(path: &Path) -> Result<(), Error> {
     }
 }
 
+/// Name of the file written alongside emitted bytecode containing the source fingerprint.
+const FINGERPRINT_FILE: &str = "fingerprint.txt";
+
+/// Computes a SHA-256 fingerprint over all `.rs` files under `src_path`, returned as a lowercase
+/// hex string. Files are hashed in path order along with their relative paths, so renames also
+/// change the fingerprint. Any `target` directory is skipped.
+///
+/// # Errors
+/// Returns `Error::IoError` if a source file cannot be read.
+pub fn compute_source_fingerprint(src_path: &Path) -> Result<String, Error> {
+    use sha2::{Digest, Sha256};
+    use std::fmt::Write;
+
+    let mut files: Vec<_> = WalkDir::new(src_path)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != "target")
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|x| x == "rs"))
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        let relative = file.strip_prefix(src_path).unwrap_or(&file);
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update(std::fs::read(&file)?);
+    }
+
+    Ok(hasher.finalize().iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    }))
+}
+
+/// Reads the fingerprint recorded in `output_dir`, if any.
+fn recorded_fingerprint(output_dir: &Path) -> Option<String> {
+    std::fs::read_to_string(output_dir.join(FINGERPRINT_FILE))
+        .ok()
+        .map(|f| f.trim().to_owned())
+}
+
+/// Returns true if `output_dir` contains bytecode compiled from the current contents of
+/// `src_path`, determined by comparing the recorded fingerprint against a fresh one. Output
+/// directories written before fingerprints were recorded have none, and are assumed to be up to
+/// date.
+pub fn is_compiled<PS: AsRef<Path>, PO: AsRef<Path>>(src_path: PS, output_dir: PO) -> bool {
+    let output_dir = output_dir.as_ref();
+    if !output_dir.exists() {
+        return false;
+    }
+    let Some(recorded) = recorded_fingerprint(output_dir) else {
+        return true;
+    };
+
+    compute_source_fingerprint(src_path.as_ref()).is_ok_and(|current| current == recorded)
+}
+
 /// Executes a cargo rustc  within the crates sources directory. This is executed within the
 /// `Roots::sources_root` directory inside a given crates version folder.
 ///
@@ -64,79 +516,441 @@ pub fn clean(path: &Path) -> Result<(), Error> {
 /// This function will panic if executing `cargo` or `rustc` fails due to OS process execution problems.
 /// It will not panic on failure of the command itself.
 ///
-/// This function will panic if the stdout or stderr from `rustc` fails to UTF-8 decode.
-///
 /// # Errors
 /// returns an instance of `Error::CompileFailed`, containing the output of stdout and stderr from the
-/// execution.
-fn compile_crate<PS: AsRef<Path>, PC: AsRef<Path>>(
+/// execution along with any parsed error codes.
+pub fn compile_crate<PS: AsRef<Path>, PC: AsRef<Path>>(
     name: &str,
     version: &str,
     src_path: PS,
     bc_root: PC,
-) -> Result<(), libpainter::Error> {
-    let fullname = format!("{}-{}", &name, version);
+    opts: &CompileOptions,
+) -> Result<(), Error> {
+    let fullname = format!("{name}-{version}");
     let output_dir = bc_root.as_ref().join(&fullname);
 
-    log::info!("Compiling: {} @ {}", &fullname, output_dir.display());
+    log::info!("Compiling: {} @ {}", fullname, output_dir.display());
+
+    check_lockfile(src_path.as_ref(), opts)?;
 
     // Build the crate with rustc, emitting llvm-bc. We also disable LTO to prevent some inlining
     // to gain better cross-crate function call introspection.
     // TODO: We should further limit optimizations and inlining to get an even better picture.
-    let output = std::process::Command::new("cargo")
-        .args([
-            "+1.67",
-            "rustc",
-            "--release",
-            "--lib",
-            "--",
-            "-g",
-            "--emit=llvm-bc",
-            "-C",
-            "lto=off",
-        ])
+    // Recovering inlined calls from `!inlinedAt` metadata is not possible yet, as `llvm_ir` only
+    // exposes the line, column and file of a `DebugLoc`, not its inlining scope.
+    let harness = HarnessGuard::write(src_path.as_ref(), opts)?;
+    let output = cargo_command(opts)
+        .arg("rustc")
+        .args(profile_args(opts))
+        .args(target_args(opts))
+        .args(build_args(opts))
+        .args(["--", "-g", "--emit=llvm-bc", "-C", "lto=off"])
         .current_dir(src_path.as_ref())
         .output()
         .unwrap();
+    drop(harness);
 
-    log::trace!("Compiled: {} with result: {:?}", fullname, output);
+    log::trace!("Compiled: {fullname} with result: {output:?}");
 
     if output.status.success() {
-        std::fs::create_dir(&output_dir);
+        std::fs::create_dir_all(&output_dir)?;
+
+        // Record the source fingerprint next to the bytecode so `is_compiled` can detect staleness.
+        let fingerprint = compute_source_fingerprint(src_path.as_ref())?;
+        std::fs::write(output_dir.join(FINGERPRINT_FILE), fingerprint)?;
 
         // If the compile succeeded, search for emitted .bc files of bytecode and copy them over
         // to the Roots::bytecode_root directory.
-        WalkDir::new(src_path.as_ref())
-            .into_iter()
-            .filter_map(Result::ok)
-            .filter(|e| e.path().extension().is_some() && e.path().extension().unwrap() == "bc")
-            .for_each(|e| {
-                let dst = output_dir.join(Path::new(e.path().file_name().unwrap()));
-                if dst.exists() {
-                    std::fs::remove_file(&dst).unwrap();
-                }
-                std::fs::copy(e.path(), &dst).unwrap();
-            });
-
-        clean(src_path.as_ref())?;
+        for bc in find_bytecode(src_path.as_ref()) {
+            let dst = output_dir.join(Path::new(bc.file_name().unwrap()));
+            if dst.exists() {
+                std::fs::remove_file(&dst).unwrap();
+            }
+            std::fs::copy(&bc, &dst).unwrap();
+        }
+
+        if !opts.incremental {
+            clean(src_path.as_ref())?;
+        }
     } else {
-        clean(src_path.as_ref())?;
+        if !opts.incremental && !opts.preserve_on_failure {
+            clean(src_path.as_ref())?;
+        }
+
+        return Err(Error::CompileFailed(CompileFailure::from_output(&output)));
+    }
+
+    Ok(())
+}
+
+/// Returns the paths of all `.bc` files found under `path`.
+fn find_bytecode(path: &Path) -> Vec<PathBuf> {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.path().extension().is_some() && e.path().extension().unwrap() == "bc")
+        .map(walkdir::DirEntry::into_path)
+        .collect()
+}
+
+/// Runs `cargo +<toolchain> rustc` on the library, or entry point harness, in `src_path`,
+/// emitting llvm-bc with LTO disabled as `compile_crate` does, with the profile from `opts` and
+/// additional cargo and rustc arguments. The bytecode is left in place and its paths returned;
+/// the caller is responsible for cleaning the crate afterwards.
+fn emit_bitcode(
+    src_path: &Path,
+    opts: &CompileOptions,
+    cargo_args: &[&str],
+    rustc_args: &[&str],
+) -> Result<Vec<PathBuf>, Error> {
+    check_lockfile(src_path, opts)?;
+
+    let harness = HarnessGuard::write(src_path, opts)?;
+    let output = cargo_command(opts)
+        .arg("rustc")
+        .args(target_args(opts))
+        .args(profile_args(opts))
+        .args(build_args(opts))
+        .args(cargo_args)
+        .args(["--", "-g", "--emit=llvm-bc", "-C", "lto=off"])
+        .args(rustc_args)
+        .current_dir(src_path)
+        .output();
+    drop(harness);
+    let output = output?;
+
+    log::trace!("Compiled: {} with result: {:?}", src_path.display(), output);
+
+    if !output.status.success() {
+        return Err(Error::CompileFailed(CompileFailure::from_output(&output)));
+    }
+
+    Ok(find_bytecode(src_path))
+}
+
+/// Directory within a crate `compile_crate_hermetic` vendors its dependencies into.
+const VENDOR_DIR: &str = "vendor";
+
+/// Compiles the crate at `src_path` as `compile_bitcode` does, but without network access, for
+/// building untrusted crates in a sandbox. Dependencies are first vendored into `vendor` with
+/// `cargo vendor`, the only step which may use the network and which is skipped once the
+/// directory exists. The build itself runs with `CompileOptions::offline` forced on and
+/// crates.io replaced by the vendored sources.
+///
+/// # Errors
+/// Returns `Error::CompileFailed` if vendoring or compilation fails, or `Error::IoError` if cargo
+/// could not be executed.
+pub fn compile_crate_hermetic(
+    src_path: &Path,
+    opts: &CompileOptions,
+) -> Result<Vec<PathBuf>, Error> {
+    if !src_path.join(VENDOR_DIR).is_dir() {
+        let output = std::process::Command::new("cargo")
+            .arg(format!("+{}", opts.toolchain))
+            .args(["vendor", VENDOR_DIR])
+            .current_dir(src_path)
+            .output()?;
+
+        log::trace!("Vendored: {} with result: {:?}", src_path.display(), output);
+
+        if !output.status.success() {
+            return Err(Error::CompileFailed(CompileFailure::from_output(&output)));
+        }
+    }
 
-        return Err(Error::CompileFailed(format!(
-            "{}\n-----------\n{}",
-            std::str::from_utf8(&output.stdout).unwrap(),
-            std::str::from_utf8(&output.stderr).unwrap()
-        )));
+    let opts = CompileOptions {
+        offline: true,
+        ..opts.clone()
     };
+    let replace_crates_io = r#"source.crates-io.replace-with="vendored-sources""#;
+    let vendored_sources = format!(r#"source.vendored-sources.directory="{VENDOR_DIR}""#);
+    emit_bitcode(
+        src_path,
+        &opts,
+        &["--config", replace_crates_io, "--config", &vendored_sources],
+        &[],
+    )
+}
+
+/// Runs `cargo check` on the crate at `src_path` with the toolchain, lockfile, features and
+/// target triple from `opts`, as a fast pre-flight check before a full bytecode build. No
+/// bytecode is emitted.
+///
+/// # Errors
+/// Returns `Error::CompileFailed` if the crate does not compile, or `Error::IoError` if cargo
+/// could not be executed.
+pub fn compile_crate_check_only(src_path: &Path, opts: &CompileOptions) -> Result<(), Error> {
+    check_lockfile(src_path, opts)?;
+
+    let output = cargo_command(opts)
+        .args(["check", "--lib"])
+        .args(build_args(opts))
+        .current_dir(src_path)
+        .output()?;
+
+    log::trace!("Checked: {} with result: {:?}", src_path.display(), output);
+
+    if !output.status.success() {
+        return Err(Error::CompileFailed(CompileFailure::from_output(&output)));
+    }
 
     Ok(())
 }
 
-/// Walks the entire `Roots::sources_root` and attempts to compile all crates in parallel.
-pub async fn compile_all<P: AsRef<Path> + Send + Sync>(
-    mut fs: CrateFs,
+/// Runs `cargo test --lib` on the crate at `src_path` with the toolchain, lockfile, features and
+/// target triple from `opts`, as a sanity check that the crate still works when built the way
+/// it will be analyzed. Tests are built in the debug profile, as they need debug info.
+///
+/// # Errors
+/// Returns `Error::CompileFailed` if the tests do not compile, `Error::TestFailed` if any test
+/// fails, or `Error::IoError` if cargo could not be executed.
+pub fn compile_crate_and_run_tests(
+    src_path: &Path,
+    opts: &CompileOptions,
+) -> Result<TestResults, Error> {
+    check_lockfile(src_path, opts)?;
+
+    let output = cargo_command(opts)
+        .args(["test", "--lib"])
+        .args(build_args(opts))
+        .current_dir(src_path)
+        .output()?;
+
+    log::trace!("Tested: {} with result: {:?}", src_path.display(), output);
+
+    let Some(results) = TestResults::from_output(&output) else {
+        return Err(Error::CompileFailed(CompileFailure::from_output(&output)));
+    };
+    if results.failed > 0 || !output.status.success() {
+        return Err(Error::TestFailed(results));
+    }
+
+    Ok(results)
+}
+
+/// Compiles the crate at `src_path` as `compile_crate` does, but leaves the emitted bytecode in
+/// place and returns its paths instead of copying it to a bytecode root.
+///
+/// # Errors
+/// Returns `Error::CompileFailed` if compilation fails, or `Error::IoError` if cargo could not be
+/// executed.
+pub fn compile_bitcode(src_path: &Path, opts: &CompileOptions) -> Result<Vec<PathBuf>, Error> {
+    emit_bitcode(src_path, opts, &[], &[])
+}
+
+/// Directory within a crate `compile_crate_for_multiple_targets` builds each target into, as
+/// `<dir>/<target triple>`.
+const MULTI_TARGET_DIR: &str = "target/multi";
+
+/// The `--target-dir` `compile_crate_for_multiple_targets` uses for the `target` triple.
+fn multi_target_dir(src_path: &Path, target: &str) -> PathBuf {
+    src_path.join(MULTI_TARGET_DIR).join(target)
+}
+
+/// Compiles the crate at `src_path` as `compile_bitcode` does once for each target triple in
+/// `targets`, in parallel, for comparing call graphs across platforms. Every build uses the
+/// toolchain and options from `opts`, overriding only `CompileOptions::target_triple`, and has
+/// its own target directory so that the builds do not wait on each other. Returns the bytecode
+/// paths or compile error of each target, keyed by its triple. With an entry point harness the
+/// builds share the harness file, so they are run one at a time instead.
+///
+/// # Errors
+/// Returns `Error::LockfileNotFound` if `opts` requires a lockfile and the crate has none.
+/// Failures of individual targets are returned in the map instead.
+pub fn compile_crate_for_multiple_targets(
+    src_path: &Path,
+    targets: &[String],
+    opts: &CompileOptions,
+) -> Result<HashMap<String, Result<Vec<PathBuf>, Error>>, Error> {
+    use rayon::prelude::*;
+
+    check_lockfile(src_path, opts)?;
+
+    let compile_target = |target: &String| {
+        let opts = CompileOptions {
+            target_triple: Some(target.clone()),
+            ..opts.clone()
+        };
+        let target_dir = multi_target_dir(src_path, target);
+        log::info!("Compiling: {} for {}", src_path.display(), target);
+        let result = emit_bitcode(
+            src_path,
+            &opts,
+            &["--target-dir", &target_dir.to_string_lossy()],
+            &[],
+        )
+        .map(|paths| {
+            paths
+                .into_iter()
+                .filter(|p| p.starts_with(&target_dir))
+                .collect()
+        });
+        (target.clone(), result)
+    };
+
+    Ok(if opts.entrypoint_harness.is_some() {
+        targets.iter().map(compile_target).collect()
+    } else {
+        targets.par_iter().map(compile_target).collect()
+    })
+}
+
+/// Compiles the crate at `src_path` with `CompileOptions::incremental` forced on, reusing the
+/// `target` directory of previous runs for much faster re-analysis. The crate is never cleaned;
+/// the returned bytecode paths point into its `target` directory.
+///
+/// # Errors
+/// Returns `Error::CompileFailed` if compilation fails, or `Error::IoError` if cargo could not be
+/// executed.
+pub fn compile_crate_incremental(
+    src_path: &Path,
+    opts: &CompileOptions,
+) -> Result<Vec<PathBuf>, Error> {
+    let opts = CompileOptions {
+        incremental: true,
+        ..opts.clone()
+    };
+
+    emit_bitcode(src_path, &opts, &[], &[])
+}
+
+/// Compiles the crate at `src_path` with the given sanitizer enabled, returning the paths of the
+/// emitted bytecode. Sanitizers are nightly only, so `+nightly` is always used regardless of
+/// `CompileOptions::toolchain`.
+///
+/// # Errors
+/// Returns `Error::CompileFailed` if compilation fails, or `Error::IoError` if cargo could not be
+/// executed.
+pub fn compile_with_sanitizer(
+    src_path: &Path,
+    sanitizer: Sanitizer,
+    opts: &CompileOptions,
+) -> Result<Vec<PathBuf>, Error> {
+    let opts = CompileOptions {
+        toolchain: "nightly".to_owned(),
+        ..opts.clone()
+    };
+
+    emit_bitcode(
+        src_path,
+        &opts,
+        &[],
+        &[&format!("-Zsanitizer={}", sanitizer.as_str())],
+    )
+}
+
+/// Compiles the crate at `src_path` with `-C instrument-coverage`, returning the paths of the
+/// instrumented bytecode. The profiling runtime calls show up as nodes flagged
+/// `FunctionNode::is_coverage_counter`, see `CallGraph::remove_coverage_nodes`.
+///
+/// # Errors
+/// Returns `Error::CompileFailed` if compilation fails, or `Error::IoError` if cargo could not be
+/// executed.
+pub fn compile_crate_with_coverage_instrumentation(src_path: &Path) -> Result<Vec<PathBuf>, Error> {
+    emit_bitcode(
+        src_path,
+        &CompileOptions::default(),
+        &[],
+        &["-C", "instrument-coverage"],
+    )
+}
+
+/// Compiles the crate at `src_path` on nightly with `-Z miri` and `-Z mir-opt-level=0`, and all
+/// LLVM optimizations disabled, returning the paths of the emitted bytecode. The resulting graph
+/// shows the unoptimized call structure, complementing the release mode analysis.
+///
+/// # Errors
+/// Returns `Error::CompileFailed` if compilation fails, or `Error::IoError` if cargo could not be
+/// executed.
+#[cfg(feature = "nightly")]
+pub fn compile_crate_with_miri_flags(src_path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let opts = CompileOptions {
+        toolchain: "nightly".to_owned(),
+        ..CompileOptions::default()
+    };
+
+    emit_bitcode(
+        src_path,
+        &opts,
+        &[],
+        &["-Zmiri", "-Zmir-opt-level=0", "-Copt-level=0"],
+    )
+}
+
+/// A crate listed in a batch manifest, see `batch_compile_from_manifest`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CrateSpec {
+    pub name: String,
+    pub version: String,
+    /// Crate source directory, relative to the manifest unless absolute.
+    pub src_path: PathBuf,
+    /// Entries with `enabled = false` are skipped.
+    #[serde(default = "CrateSpec::default_enabled")]
+    pub enabled: bool,
+}
+impl CrateSpec {
+    fn default_enabled() -> bool {
+        true
+    }
+}
+
+/// The `[[crates]]` tables of a batch manifest.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct BatchManifest {
+    crates: Vec<CrateSpec>,
+}
+
+/// Compiles every enabled crate listed in the TOML manifest at `manifest_path` in parallel, as
+/// `compile_bitcode` does with the default `CompileOptions`. Each entry is a `[[crates]]` table
+/// with `name`, `version`, `src_path` and an optional `enabled` key. Returns each compiled crate,
+/// with its `src_path` resolved, along with its bytecode paths or compile error, in manifest
+/// order.
+///
+/// # Errors
+/// Returns `Error::IoError` if the manifest cannot be read, or `Error::ManifestError` if it is not
+/// a valid batch manifest. Failures of individual crates are returned in the results instead.
+pub fn batch_compile_from_manifest(
+    manifest_path: &Path,
+) -> Result<Vec<(CrateSpec, BatchResult)>, Error> {
+    use rayon::prelude::*;
+
+    let manifest: BatchManifest = toml::from_str(&std::fs::read_to_string(manifest_path)?)
+        .map_err(|e| Error::ManifestError(e.to_string()))?;
+    let base = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let opts = CompileOptions::default();
+
+    Ok(manifest
+        .crates
+        .into_iter()
+        .filter(|spec| spec.enabled)
+        .map(|spec| CrateSpec {
+            src_path: base.join(&spec.src_path),
+            ..spec
+        })
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|spec| {
+            log::info!("Compiling: {}-{}", spec.name, spec.version);
+            let result = compile_bitcode(&spec.src_path, &opts);
+            (spec, result)
+        })
+        .collect())
+}
+
+/// Walks the entire `Roots::sources_root` and attempts to compile all crates in parallel with
+/// `opts`.
+///
+/// # Errors
+/// Returns `Error::IndexError` if the local crates.io index cannot be opened. Failures of
+/// individual crates are logged and skipped.
+///
+/// # Panics
+/// Panics if a compile panicked while holding the `CrateFs` lock.
+pub fn compile_all<P: AsRef<Path> + Send + Sync>(
+    fs: CrateFs,
     bc_root: P,
     update_only: bool,
+    opts: &CompileOptions,
 ) -> Result<(), Error> {
     use rayon::iter::ParallelIterator;
 
@@ -152,10 +966,12 @@ pub async fn compile_all<P: AsRef<Path> + Send + Sync>(
         let v = c.highest_version();
 
         let fullname = format!("{}-{}", c.name(), v.version());
-        log::trace!("Opening: {}", fullname);
+        log::trace!("Opening: {fullname}");
 
-        if update_only && bc_root.join(&fullname).exists() {
-            log::info!("{} bytecode exists, skipping..", &fullname);
+        // Checking the fingerprint requires the sources, so skip opening them when there is none.
+        let output_dir = bc_root.join(&fullname);
+        if update_only && output_dir.exists() && recorded_fingerprint(&output_dir).is_none() {
+            log::info!("{fullname} bytecode exists, skipping..");
             return;
         }
 
@@ -164,23 +980,193 @@ pub async fn compile_all<P: AsRef<Path> + Send + Sync>(
             if let Ok(entry) = lock.open(&fullname) {
                 entry.path().to_path_buf()
             } else {
-                log::error!("Opening failed on {}", fullname);
+                log::error!("Opening failed on {fullname}");
                 return;
             }
         };
 
-        if let Err(e) = compile_crate(c.name(), v.version(), &cache, &bc_root) {
-            log::error!("{:?}", e);
+        if update_only && is_compiled(&cache, &output_dir) {
+            log::info!("{fullname} bytecode is up to date, skipping..");
+            return;
+        }
+
+        if let Err(e) = compile_crate(c.name(), v.version(), &cache, &bc_root, opts) {
+            log::error!("{e:?}");
         }
         //}
     };
 
     index
         .crates_parallel()
-        .filter_map(|c| c.ok())
+        .filter_map(std::result::Result::ok)
         .for_each(|c| {
             do_crate(c, fs.clone(), bc_root.as_ref().to_path_buf());
         });
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty scratch directory for the test `name` under the system temp directory.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("painter-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn fingerprint_changes_with_source() {
+        let src = scratch_dir("fingerprint");
+        std::fs::write(src.join("src/lib.rs"), "pub fn a() {}").unwrap();
+
+        let before = compute_source_fingerprint(&src).unwrap();
+        assert_eq!(before, compute_source_fingerprint(&src).unwrap());
+
+        std::fs::write(src.join("src/lib.rs"), "pub fn b() {}").unwrap();
+        assert_ne!(before, compute_source_fingerprint(&src).unwrap());
+
+        std::fs::remove_dir_all(&src).unwrap();
+    }
+
+    #[test]
+    fn is_compiled_compares_recorded_fingerprint() {
+        let src = scratch_dir("is-compiled");
+        std::fs::write(src.join("src/lib.rs"), "pub fn a() {}").unwrap();
+        let output_dir = src.join("bc");
+        assert!(!is_compiled(&src, &output_dir));
+
+        // Output from before fingerprints were recorded
+        std::fs::create_dir(&output_dir).unwrap();
+        assert!(is_compiled(&src, &output_dir));
+
+        let fingerprint = compute_source_fingerprint(&src).unwrap();
+        std::fs::write(output_dir.join(FINGERPRINT_FILE), fingerprint).unwrap();
+        assert!(is_compiled(&src, &output_dir));
+
+        std::fs::write(src.join("src/lib.rs"), "pub fn b() {}").unwrap();
+        assert!(!is_compiled(&src, &output_dir));
+
+        std::fs::remove_dir_all(&src).unwrap();
+    }
+
+    #[test]
+    fn rustflags_merge_after_inherited_flags() {
+        let extra = ["-Cdebuginfo=2".to_owned(), "--cfg=painter".to_owned()];
+        assert_eq!(
+            merge_rustflags(Some("-Cfoo\x1f-Cbar"), Some("-Cbaz"), &[]),
+            None
+        );
+        assert_eq!(merge_rustflags(None, None, &[]), None);
+
+        // Encoded flags win over `RUSTFLAGS`, which cargo would ignore.
+        assert_eq!(
+            merge_rustflags(Some("-Cfoo\x1f-C bar"), Some("-Cbaz"), &extra).unwrap(),
+            "-Cfoo\x1f-C bar\x1f-Cdebuginfo=2\x1f--cfg=painter"
+        );
+        assert_eq!(
+            merge_rustflags(Some(""), Some(" -Cbaz  -Cqux "), &extra).unwrap(),
+            "-Cbaz\x1f-Cqux\x1f-Cdebuginfo=2\x1f--cfg=painter"
+        );
+        assert_eq!(
+            merge_rustflags(None, None, &extra).unwrap(),
+            "-Cdebuginfo=2\x1f--cfg=painter"
+        );
+    }
+
+    #[test]
+    fn each_target_has_its_own_target_dir() {
+        let src = Path::new("/crates/foo-0.1.0");
+        let linux = multi_target_dir(src, "x86_64-unknown-linux-gnu");
+        let wasm = multi_target_dir(src, "wasm32-unknown-unknown");
+        assert_ne!(linux, wasm);
+        assert!(!linux.starts_with(&wasm) && !wasm.starts_with(&linux));
+        assert!(linux.starts_with(src.join("target")));
+    }
+
+    #[test]
+    #[ignore = "needs the x86_64 and i686 linux targets installed"]
+    fn compiles_for_two_targets() {
+        let src = scratch_dir("multi-target");
+        std::fs::write(
+            src.join("Cargo.toml"),
+            "[package]\nname = \"multi\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        std::fs::write(src.join("src/lib.rs"), "pub fn a() {}").unwrap();
+
+        let targets = [
+            "x86_64-unknown-linux-gnu".to_owned(),
+            "i686-unknown-linux-gnu".to_owned(),
+        ];
+        let results =
+            compile_crate_for_multiple_targets(&src, &targets, &CompileOptions::default()).unwrap();
+        for target in &targets {
+            let paths = results[target].as_ref().unwrap();
+            assert!(!paths.is_empty());
+            assert!(paths
+                .iter()
+                .all(|p| p.starts_with(multi_target_dir(&src, target))));
+        }
+
+        std::fs::remove_dir_all(&src).unwrap();
+    }
+
+    #[test]
+    #[ignore = "compiles a crate twice with the default toolchain"]
+    fn incremental_rebuild_is_faster() {
+        let src = scratch_dir("incremental");
+        std::fs::write(
+            src.join("Cargo.toml"),
+            "[package]\nname = \"incremental\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        std::fs::write(src.join("src/lib.rs"), "pub fn a() -> u32 { 1 }").unwrap();
+
+        let opts = CompileOptions::default();
+        let start = std::time::Instant::now();
+        let first = compile_crate_incremental(&src, &opts).unwrap();
+        let cold = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let second = compile_crate_incremental(&src, &opts).unwrap();
+        let warm = start.elapsed();
+
+        assert!(warm < cold, "rebuild took {warm:?}, first build {cold:?}");
+        assert!(!second.is_empty());
+        let debug = src.join("target/debug");
+        assert!(first.iter().chain(&second).all(|p| p.starts_with(&debug)));
+
+        std::fs::remove_dir_all(&src).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "nightly-tests")]
+    fn address_sanitizer_hooks_are_flagged() {
+        let src = scratch_dir("asan");
+        std::fs::write(
+            src.join("Cargo.toml"),
+            "[package]\nname = \"asan\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            src.join("src/lib.rs"),
+            "pub fn sum(v: &[u64]) -> u64 { v.iter().sum() }",
+        )
+        .unwrap();
+
+        let bc =
+            compile_with_sanitizer(&src, Sanitizer::Address, &CompileOptions::default()).unwrap();
+        let graph = crate::analysis::extract_calls_from_files(
+            &bc,
+            &crate::analysis::AnalysisConfig::default(),
+        )
+        .unwrap();
+        assert!(graph.nodes().any(|node| node.is_sanitizer_hook));
+
+        std::fs::remove_dir_all(&src).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use crate::Error;
+use std::path::PathBuf;
+
+/// Commented template written by `painter config init`.
+const CONFIG_TEMPLATE: &str = r#"# User level defaults for painter.
+# Command line flags always take precedence.
+
+# Toolchain used to compile crates, passed to cargo as `+<toolchain>`.
+# default_toolchain = "1.67"
+
+# Function name prefixes to drop from call graphs, in addition to the built in ones.
+# extra_blocked_prefixes = ["tokio::", "tracing::"]
+
+# Format used when writing call graphs: "text", "json", "jgf", "gexf", "graphml", "dot",
+# "csv", "tsv" (or "text-table"), "mermaid", "plantuml", "cypher", "gremlin", "sigma", "d3",
+# "cytoscape", "flamegraph", "html" or, with the msgpack and excel features, "msgpack" and
+# "xlsx".
+# default_output_format = "text"
+"#;
+
+/// Formats a `CallGraph` can be written in.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// `CallGraph::to_text_report`.
+    #[default]
+    Text,
+    /// The `serde` representation of `CallGraph`, readable with `serde_json::from_reader`.
+    Json,
+    /// `CallGraph::to_jgf_json`.
+    Jgf,
+    /// `CallGraph::to_gexf_xml`.
+    Gexf,
+    /// `CallGraph::to_graphml`.
+    #[value(name = "graphml")]
+    GraphMl,
+    /// `CallGraph::to_hierarchical_dot`.
+    Dot,
+    /// `CallGraph::to_csv`.
+    Csv,
+    /// `CallGraph::to_tsv`.
+    #[serde(alias = "text-table")]
+    #[value(alias = "text-table")]
+    Tsv,
+    /// `CallGraph::to_mermaid`.
+    Mermaid,
+    /// `CallGraph::to_plantuml`.
+    #[value(name = "plantuml")]
+    PlantUml,
+    /// `CallGraph::export_to_neo4j_cypher`.
+    Cypher,
+    /// `CallGraph::to_gremlin_groovy`.
+    Gremlin,
+    /// `CallGraph::to_sigma_json`.
+    #[serde(rename = "sigma")]
+    #[value(name = "sigma")]
+    SigmaJson,
+    /// `CallGraph::to_d3_json`.
+    #[serde(rename = "d3")]
+    #[value(name = "d3")]
+    D3Json,
+    /// `CallGraph::to_cytoscape_json`.
+    #[serde(rename = "cytoscape")]
+    #[value(name = "cytoscape")]
+    CytoscapeJson,
+    /// `CallGraph::to_folded_stacks`.
+    #[serde(rename = "flamegraph")]
+    #[value(name = "flamegraph")]
+    FlamegraphData,
+    /// `CallGraph::to_interactive_html`.
+    Html,
+    /// `CallGraph::export_to_msgpack`.
+    #[cfg(feature = "msgpack")]
+    #[value(name = "msgpack")]
+    MsgPack,
+    /// `CallGraph::to_excel`.
+    #[cfg(feature = "excel")]
+    Xlsx,
+}
+
+/// User level defaults read from `~/.config/painter/config.toml`, see `load_user_config`.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct PainterConfig {
+    /// Toolchain used when none is given on the command line.
+    pub default_toolchain: Option<String>,
+    /// Merged into `AnalysisConfig::extra_blocked_prefixes`.
+    pub extra_blocked_prefixes: Vec<String>,
+    /// Format used when none is given on the command line.
+    pub default_output_format: OutputFormat,
+}
+
+/// Path of the user config file, `painter/config.toml` within the platform config directory.
+///
+/// # Errors
+/// Returns `Error::ConfigError` if the platform has no config directory.
+pub fn user_config_path() -> Result<PathBuf, Error> {
+    dirs::config_dir()
+        .map(|dir| dir.join("painter").join("config.toml"))
+        .ok_or_else(|| Error::ConfigError("no user config directory".to_owned()))
+}
+
+/// Reads the user config file. A missing file yields the default config.
+///
+/// # Errors
+/// Returns `Error::IoError` if the file exists but cannot be read, and `Error::ConfigError` if it
+/// is not valid TOML or no config directory exists.
+pub fn load_user_config() -> Result<PainterConfig, Error> {
+    let path = user_config_path()?;
+    if !path.exists() {
+        return Ok(PainterConfig::default());
+    }
+
+    toml::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|e| Error::ConfigError(format!("{}: {e}", path.display())))
+}
+
+/// Writes the commented config template to `user_config_path`, returning its path. An existing
+/// config is never overwritten.
+///
+/// # Errors
+/// Returns `Error::IoError` if the file already exists or cannot be written, and
+/// `Error::ConfigError` if no config directory exists.
+pub fn init_user_config() -> Result<PathBuf, Error> {
+    let path = user_config_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, CONFIG_TEMPLATE.as_bytes()))?;
+
+    Ok(path)
+}
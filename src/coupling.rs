@@ -0,0 +1,58 @@
+use crate::graph::{crate_name, CallGraph};
+use ndarray::Array2;
+use std::collections::{BTreeSet, HashMap};
+
+/// Computes the crate coupling matrix of a set of crates, where `C[i][j]` is the number of calls
+/// from functions of crate `i` to functions of crate `j`, summed over all graphs. Returns the
+/// matrix along with the crate name of each row and column, sorted by name.
+///
+/// Crates are matched by the first path segment of the demangled names, so `-` in the map keys is
+/// treated as `_`. Calls into crates outside of `graphs` are ignored.
+#[must_use]
+pub fn compute_crate_coupling_matrix<S: std::hash::BuildHasher>(
+    graphs: &HashMap<String, CallGraph, S>,
+) -> (Array2<f64>, Vec<String>) {
+    let index: Vec<String> = graphs
+        .keys()
+        .map(|name| name.replace('-', "_"))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let positions: HashMap<&str, usize> = index
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), i))
+        .collect();
+
+    let mut matrix = Array2::zeros((index.len(), index.len()));
+    for (caller, callee, count) in graphs.values().flat_map(CallGraph::edges) {
+        if let (Some(&i), Some(&j)) = (
+            positions.get(crate_name(caller)),
+            positions.get(crate_name(callee)),
+        ) {
+            matrix[[i, j]] += f64::from(count);
+        }
+    }
+
+    (matrix, index)
+}
+
+/// Prints a coupling matrix returned by `compute_crate_coupling_matrix` as a table, with callers
+/// as rows and callees as columns.
+pub fn print_coupling_matrix(matrix: &Array2<f64>, index: &[String]) {
+    let width = index.iter().map(String::len).max().unwrap_or(0).max(8);
+
+    print!("{:width$}", "");
+    for name in index {
+        print!("  {name:>width$}");
+    }
+    println!();
+
+    for (name, row) in index.iter().zip(matrix.rows()) {
+        print!("{name:width$}");
+        for value in row {
+            print!("  {value:>width$}");
+        }
+        println!();
+    }
+}
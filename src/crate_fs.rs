@@ -1,9 +1,6 @@
 #![allow(clippy::module_name_repetitions)]
 use circular_buffer::CircularBuffer;
-use std::{
-    path::{Path, PathBuf},
-    sync::Mutex,
-};
+use std::path::{Path, PathBuf};
 
 /// Top error type returned during any stage of analysis from compile to data import.
 #[derive(thiserror::Error, Debug)]
@@ -18,6 +15,8 @@ pub enum Error {
     CrateFileNotFound,
     #[error("CrateFileNotFound")]
     ExtractionFailed,
+    #[error("Download failed: {0}")]
+    DownloadFailed(String),
     #[error(
         "Crate name contained invalid characters or did not match the NAME-VER format. Name: {0}"
     )]
@@ -29,6 +28,10 @@ pub struct CrateEntry {
     pub full_name: String,
 }
 impl CrateEntry {
+    /// Creates an entry for a crate named `NAME-VER`, e.g. `serde-1.0.0`.
+    ///
+    /// # Errors
+    /// Returns `Error::CrateNameError` if `full_name` has no `-` separating name and version.
     pub fn new(full_name: String) -> Result<Self, Error> {
         let (_, _) = full_name
             .rsplit_once('-')
@@ -38,18 +41,30 @@ impl CrateEntry {
 
         Ok(Self { full_name })
     }
+    #[must_use]
     pub fn full_name(&self) -> &str {
         &self.full_name
     }
 
+    /// The crate name, everything before the last `-` of the full name.
+    ///
+    /// # Panics
+    /// Panics if `full_name` was set to a value without a `-`, which `CrateEntry::new` rejects.
+    #[must_use]
     pub fn name(&self) -> &str {
         self.full_name.rsplit_once('-').unwrap().0
     }
 
+    /// The crate version, everything after the last `-` of the full name.
+    ///
+    /// # Panics
+    /// Panics if `full_name` was set to a value without a `-`, which `CrateEntry::new` rejects.
+    #[must_use]
     pub fn version(&self) -> &str {
         self.full_name.rsplit_once('-').unwrap().1
     }
 
+    #[must_use]
     pub fn filename(&self) -> String {
         format!("{}.crate", self.full_name())
     }
@@ -65,11 +80,16 @@ where
 
 #[derive(Debug)]
 pub struct CrateCache {
-    src_crate_file: PathBuf,
     extracted_path: PathBuf,
     no_delete: bool,
 }
 impl CrateCache {
+    /// Extracts the `.crate` file of `entry` from `crates_dir` into `sources_dir`, reusing a
+    /// previous extraction if one exists.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if the crate file cannot be read or unpacked, or
+    /// `Error::ExtractionFailed` if it did not unpack into a directory named after `entry`.
     pub fn new<P>(entry: &CrateEntry, crates_dir: P, sources_dir: P) -> Result<Self, Error>
     where
         P: AsRef<Path>,
@@ -79,7 +99,6 @@ impl CrateCache {
 
         if extracted_path.exists() {
             return Ok(Self {
-                src_crate_file,
                 extracted_path,
                 no_delete: true,
             });
@@ -101,19 +120,66 @@ impl CrateCache {
         }
 
         Ok(Self {
-            src_crate_file,
             extracted_path,
             no_delete: false,
         })
     }
 
+    #[must_use]
     pub fn path(&self) -> &Path {
         &self.extracted_path
     }
+
+    /// Keeps the extracted sources on disk when this cache is dropped.
+    pub fn preserve(&mut self) {
+        self.no_delete = true;
+    }
+}
+
+/// Downloads the `.crate` file of `entry` from crates.io into `crates_dir` with `curl`, returning
+/// its path. Nothing is downloaded if the file already exists.
+///
+/// # Errors
+/// Returns `Error::DownloadFailed` if `curl` exits unsuccessfully, or `Error::IoError` if it could
+/// not be executed.
+pub fn download_crate<P: AsRef<Path>>(entry: &CrateEntry, crates_dir: P) -> Result<PathBuf, Error> {
+    let path = crates_dir.as_ref().join(entry.filename());
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let url = format!(
+        "https://static.crates.io/crates/{}/{}",
+        entry.name(),
+        entry.filename()
+    );
+    log::trace!("Downloading: {} -> {}", url, path.display());
+
+    let output = std::process::Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--output",
+        ])
+        .arg(&path)
+        .arg(&url)
+        .output()?;
+    if !output.status.success() {
+        // curl may leave a partial file behind on failure
+        let _ = std::fs::remove_file(&path);
+        return Err(Error::DownloadFailed(format!(
+            "{url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(path)
 }
 impl Drop for CrateCache {
     fn drop(&mut self) {
-        log::trace!("dropping {:?}", self);
+        log::trace!("dropping {self:?}");
         if !self.no_delete {
             std::fs::remove_dir_all(&self.extracted_path).unwrap();
         }
@@ -125,6 +191,11 @@ pub struct CrateFsConfig {
     pub extract_path: PathBuf,
 }
 impl CrateFsConfig {
+    /// Creates a config reading `.crate` files from `crates_path` and extracting them into
+    /// `extract_path`.
+    ///
+    /// # Panics
+    /// Panics if either path does not exist.
     pub fn with_paths<P1, P2>(crates_path: P1, extract_path: P2) -> Self
     where
         P1: Into<PathBuf>,
@@ -146,18 +217,16 @@ impl CrateFsConfig {
 
 pub struct CrateFs {
     cache: Box<CircularBuffer<1024, (CrateEntry, CrateCache)>>,
-    index: crates_index::Index,
     config: CrateFsConfig,
 }
 impl CrateFs {
-    pub fn new(config: CrateFsConfig) -> Result<Self, Error> {
-        let index = crates_index::Index::new_cargo_default()?;
-
-        Ok(Self {
+    /// Creates an empty cache over `config`.
+    #[must_use]
+    pub fn new(config: CrateFsConfig) -> Self {
+        Self {
             cache: CircularBuffer::boxed(),
             config,
-            index,
-        })
+        }
     }
 
     fn find_cache_index(&self, entry: &CrateEntry) -> Option<usize> {
@@ -171,6 +240,12 @@ impl CrateFs {
             },
         )
     }
+    /// Removes the crate `fullname` from the cache, deleting its extracted sources unless they
+    /// were preserved.
+    ///
+    /// # Errors
+    /// Returns `Error::CrateNameError` if `fullname` is not a valid crate name, or
+    /// `Error::CrateNotFound` if it is not open.
     pub fn close<S: AsRef<str>>(&mut self, fullname: S) -> Result<(), Error> {
         let entry = CrateEntry::new(fullname.as_ref().to_string())?;
 
@@ -180,6 +255,13 @@ impl CrateFs {
         Ok(())
     }
 
+    /// Returns the cached extraction of the crate `fullname`, extracting it first if it is not
+    /// open yet.
+    ///
+    /// # Errors
+    /// Returns `Error::CrateNameError` if `fullname` is not a valid crate name,
+    /// `Error::CrateFileNotFound` if its `.crate` file is missing, or any error of
+    /// `CrateCache::new`.
     pub fn open<S: AsRef<str>>(&mut self, fullname: S) -> Result<&CrateCache, Error> {
         let entry = CrateEntry::new(fullname.as_ref().to_string())?;
 
@@ -205,17 +287,8 @@ impl CrateFs {
         }
     }
 
+    #[must_use]
     pub fn config(&self) -> &CrateFsConfig {
         &self.config
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn init_logging() {
-        // capture log messages with test harness
-        let _ = env_logger::builder().is_test(true).try_init();
-    }
-}
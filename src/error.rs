@@ -1,4 +1,4 @@
-use crate::{crate_fs, db, index};
+use crate::{compile, crate_fs, db, index};
 
 /// Top error type returned during any stage of analysis from compile to data import.
 #[derive(thiserror::Error, Debug)]
@@ -15,6 +15,12 @@ pub enum Error {
     #[error("LLVM IR failure: {0}")]
     LLVMError(String),
     ///
+    #[error("Analysis of {} timed out after {elapsed:?}", path.display())]
+    AnalysisTimeout {
+        path: std::path::PathBuf,
+        elapsed: std::time::Duration,
+    },
+    ///
     #[error("Database Error: {0}")]
     DbError(#[from] db::Error),
     ///
@@ -24,6 +30,51 @@ pub enum Error {
     #[error("Indexing Error: {0}")]
     CrateFsError(#[from] crate_fs::Error),
     ///
+    #[error("Rustdoc failure: {0}")]
+    RustdocError(String),
+    ///
+    #[error("Clippy failure: {0}")]
+    ClippyError(String),
+    ///
+    #[error("Compile Error: {0}")]
+    CompileError(#[from] compile::Error),
+    ///
+    #[error("Watch Error: {0}")]
+    WatchError(#[from] notify::Error),
+    ///
+    #[error("Invalid pattern: {0}")]
+    RegexError(#[from] regex::Error),
+    ///
+    #[error("Object file Error: {0}")]
+    ObjectError(#[from] object::Error),
+    ///
+    #[error("Lockfile Error: {0}")]
+    LockfileError(#[from] cargo_lock::Error),
+    ///
+    #[error("WebAssembly Error: {0}")]
+    WasmError(#[from] wasmparser::BinaryReaderError),
+    ///
+    #[error("PDB Error: {0}")]
+    PdbError(#[from] pdb::Error),
+    ///
+    #[error("cargo metadata failed: {0}")]
+    MetadataError(String),
+    ///
+    #[error("Config Error: {0}")]
+    ConfigError(String),
+    ///
+    #[error("Serialization Error: {0}")]
+    SerializationError(String),
+    ///
+    #[error("Edge already exists: {0} -> {1}")]
+    EdgeExists(String, String),
+    ///
+    #[error("Function not found in call graph: {0}")]
+    UnknownFunction(String),
+    ///
+    #[error("Invalid profile data on line {0}: {1}")]
+    ProfileDataError(usize, String),
+    ///
     #[error("MissingCompressedPath")]
     MissingCompressedPath,
     ///
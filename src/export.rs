@@ -0,0 +1,1715 @@
+use crate::{
+    config::OutputFormat,
+    crate_fs::CrateEntry,
+    graph::{compute_crate_instability_metric, crate_name, is_extern_c, CallGraph},
+    Error,
+};
+use std::{collections::HashMap, io::Write};
+
+/// Options controlling the Cypher script produced by `CallGraph::export_to_neo4j_cypher`.
+#[derive(Debug, Clone)]
+pub struct Neo4jOptions {
+    /// Number of rows passed to each `UNWIND` statement.
+    pub batch_size: usize,
+    /// Database selected with `:use` at the top of the script.
+    pub database_name: String,
+}
+impl Default for Neo4jOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 1000,
+            database_name: "neo4j".to_owned(),
+        }
+    }
+}
+
+/// Escapes a string for use inside a double quoted Cypher string literal.
+fn cypher_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Node metrics which `CallGraph::assign_colors_by_metric` can color by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMetric {
+    ByOutDegree,
+    ByInDegree,
+    /// Total number of calls into the function.
+    ByCallCount,
+    ByCrateName,
+    /// Red for unsafe functions, see `FunctionNode::is_unsafe`, and green for the rest.
+    ByUnsafe,
+}
+
+/// Categorical palette used for `ColorMetric::ByCrateName`, repeated once exhausted.
+const CRATE_PALETTE: [[u8; 3]; 10] = [
+    [31, 119, 180],
+    [255, 127, 14],
+    [44, 160, 44],
+    [214, 39, 40],
+    [148, 103, 189],
+    [140, 86, 75],
+    [227, 119, 194],
+    [127, 127, 127],
+    [188, 189, 34],
+    [23, 190, 207],
+];
+
+/// Maps `t` in `[0, 1]` onto a green, yellow, red gradient.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn gradient(t: f64) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        [(510.0 * t).round() as u8, 255, 0]
+    } else {
+        [255, (510.0 * (1.0 - t)).round() as u8, 0]
+    }
+}
+
+/// Escapes a string for use inside a double quoted DOT identifier.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Number of additions chained into each statement of `CallGraph::to_gremlin_groovy`.
+const GREMLIN_BATCH_SIZE: usize = 500;
+
+/// Header row of `CallGraph::to_tsv`.
+const TSV_HEADER: &str = "caller\tcallee\tcall_count";
+
+/// Escapes a string for use inside a single quoted Groovy string literal.
+fn groovy_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// Page written by `CallGraph::to_interactive_html`, with `{{TITLE}}` and `{{DATA}}` placeholders.
+const INTERACTIVE_HTML_TEMPLATE: &str = include_str!("templates/interactive.html");
+
+/// Quotes a string as a single quoted YAML scalar.
+fn yaml_escape(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Escapes a string for use in XML text and attribute values.
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Escapes a string for use inside a double quoted Mermaid label, using its HTML entity codes.
+fn mermaid_escape(s: &str) -> String {
+    s.replace('#', "#35;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+/// Escapes a string for use inside a double quoted `PlantUML` string, using its `~` escape.
+fn plantuml_escape(s: &str) -> String {
+    s.replace('~', "~~").replace('"', "~\"")
+}
+
+/// A titled section of `CallGraph::to_text_report`, computing a value per function or call.
+type ReportSection<T> = (&'static str, fn(&CallGraph, &str) -> T);
+
+/// Stable identifier for a node, derived from a SHA-256 prefix of its demangled name.
+fn node_id(demangled: &str) -> String {
+    use sha2::{Digest, Sha256};
+    use std::fmt::Write;
+
+    Sha256::digest(demangled.as_bytes())
+        .iter()
+        .take(8)
+        .fold(String::new(), |mut id, b| {
+            let _ = write!(id, "{b:02x}");
+            id
+        })
+}
+
+impl CallGraph {
+    /// Writes a Cypher script which imports this graph into a Neo4j database. Nodes become
+    /// `(:Function { name, unsafe })` and edges `(:Function)-[:CALLS { count }]->(:Function)`, both
+    /// merged in batches of `Neo4jOptions::batch_size` rows via `UNWIND`.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn export_to_neo4j_cypher<W: Write>(
+        &self,
+        mut w: W,
+        opts: &Neo4jOptions,
+    ) -> Result<(), Error> {
+        let batch_size = opts.batch_size.max(1);
+
+        writeln!(w, ":use {};", opts.database_name)?;
+
+        let nodes: Vec<_> = self.nodes().collect();
+        for batch in nodes.chunks(batch_size) {
+            let rows: Vec<_> = batch
+                .iter()
+                .map(|n| {
+                    format!(
+                        "{{name: \"{}\", unsafe: {}}}",
+                        cypher_escape(&n.demangled),
+                        n.is_unsafe
+                    )
+                })
+                .collect();
+            writeln!(
+                w,
+                "UNWIND [{}] AS row MERGE (n:Function {{name: row.name}}) SET n.unsafe = row.unsafe;",
+                rows.join(", ")
+            )?;
+        }
+
+        let edges: Vec<_> = self.edges().collect();
+        for batch in edges.chunks(batch_size) {
+            let rows: Vec<_> = batch
+                .iter()
+                .map(|(caller, callee, count)| {
+                    format!(
+                        "{{caller: \"{}\", callee: \"{}\", count: {}}}",
+                        cypher_escape(caller),
+                        cypher_escape(callee),
+                        count
+                    )
+                })
+                .collect();
+            writeln!(
+                w,
+                "UNWIND [{}] AS row MATCH (a:Function {{name: row.caller}}) MATCH (b:Function {{name: row.callee}}) MERGE (a)-[r:CALLS]->(b) SET r.count = row.count;",
+                rows.join(", ")
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a Gremlin-Groovy script which imports this graph into Amazon Neptune or Azure
+    /// Cosmos DB. Nodes become `Function` vertices keyed by `node_id` with `name` and `unsafe`
+    /// properties, and edges `calls` edges with a `count` property. Additions are chained into
+    /// one traversal per batch of 500, so each line of the script is submitted as a single
+    /// transaction.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn to_gremlin_groovy<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        let nodes: Vec<_> = self.nodes().collect();
+        for batch in nodes.chunks(GREMLIN_BATCH_SIZE) {
+            let steps: Vec<_> = batch
+                .iter()
+                .map(|n| {
+                    format!(
+                        "addV('Function').property(id, '{}').property('name', '{}').property('unsafe', {})",
+                        node_id(&n.demangled),
+                        groovy_escape(&n.demangled),
+                        n.is_unsafe
+                    )
+                })
+                .collect();
+            writeln!(w, "g.{}.iterate()", steps.join("."))?;
+        }
+
+        let edges: Vec<_> = self.edges().collect();
+        for batch in edges.chunks(GREMLIN_BATCH_SIZE) {
+            let steps: Vec<_> = batch
+                .iter()
+                .map(|(caller, callee, count)| {
+                    format!(
+                        "V('{}').addE('calls').to(__.V('{}')).property('count', {count})",
+                        node_id(caller),
+                        node_id(callee)
+                    )
+                })
+                .collect();
+            writeln!(w, "g.{}.iterate()", steps.join("."))?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the edges of the graph as tab separated `caller`, `callee` and `call_count` columns
+    /// after a header row, as specified in `FORMAT.md`.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn to_tsv<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        writeln!(w, "{TSV_HEADER}")?;
+        for (caller, callee, count) in self.edges() {
+            writeln!(w, "{caller}\t{callee}\t{count}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the edges of the graph as comma separated `caller`, `callee` and `call_count`
+    /// columns after a header row, quoting fields as described in RFC 4180.
+    ///
+    /// # Errors
+    /// Returns `Error::SerializationError` if writing to `w` fails.
+    pub fn to_csv<W: Write>(&self, w: W) -> Result<(), Error> {
+        let mut writer = csv::Writer::from_writer(w);
+        writer
+            .write_record(["caller", "callee", "call_count"])
+            .and_then(|()| {
+                self.edges().try_for_each(|(caller, callee, count)| {
+                    writer.write_record([caller, callee, &count.to_string()])
+                })
+            })
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+        Ok(writer.flush()?)
+    }
+
+    /// Reads a graph written by `to_tsv`. The header row is optional and blank lines are skipped.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if reading fails, or `Error::SerializationError` for a row without
+    /// exactly three columns or with an invalid call count.
+    pub fn from_tsv<R: std::io::Read>(r: R) -> Result<CallGraph, Error> {
+        use std::io::BufRead;
+
+        let mut graph = CallGraph::new();
+        for (i, line) in std::io::BufReader::new(r).lines().enumerate() {
+            let line = line?;
+            if line.is_empty() || (i == 0 && line == TSV_HEADER) {
+                continue;
+            }
+
+            let malformed =
+                || Error::SerializationError(format!("malformed TSV row {}: {line}", i + 1));
+            let mut columns = line.split('\t');
+            let (Some(caller), Some(callee), Some(count), None) = (
+                columns.next(),
+                columns.next(),
+                columns.next(),
+                columns.next(),
+            ) else {
+                return Err(malformed());
+            };
+            let count = count.parse().map_err(|_| malformed())?;
+
+            graph.add_edge_with_count(caller, callee, count);
+        }
+
+        Ok(graph)
+    }
+
+    /// Writes the graph as a JSON Graph Format (JGF) document. Nodes are keyed by `node_id` and
+    /// carry their demangled name, unsafe flag and in-degree as metadata; edges have a `calls`
+    /// relation with the call count as metadata.
+    ///
+    /// # Errors
+    /// Returns `Error::SerializationError` if serializing or writing to `w` fails.
+    pub fn to_jgf_json<W: Write>(&self, w: W) -> Result<(), Error> {
+        let nodes: serde_json::Map<_, _> = self
+            .nodes()
+            .map(|n| {
+                (
+                    node_id(&n.demangled),
+                    serde_json::json!({
+                        "label": n.demangled,
+                        "metadata": {
+                            "demangled": n.demangled,
+                            "unsafe": n.is_unsafe,
+                            "in_degree": self.in_degree(&n.demangled),
+                            "synthetic": n.is_synthetic,
+                        },
+                    }),
+                )
+            })
+            .collect();
+
+        let edges: Vec<_> = self
+            .edges()
+            .map(|(caller, callee, count)| {
+                serde_json::json!({
+                    "source": node_id(caller),
+                    "target": node_id(callee),
+                    "relation": "calls",
+                    "directed": true,
+                    "metadata": {
+                        "count": count,
+                        "synthetic": self.is_synthetic_edge(caller, callee),
+                    },
+                })
+            })
+            .collect();
+
+        let document = serde_json::json!({
+            "graph": {
+                "id": "painter",
+                "directed": true,
+                "nodes": nodes,
+                "edges": edges,
+            }
+        });
+
+        serde_json::to_writer_pretty(w, &document)
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Writes the graph in the JSON format imported by Sigma.js. Nodes are laid out evenly on
+    /// the unit circle and sized by `out_degree + 1`; edges are sized by their call count.
+    ///
+    /// # Errors
+    /// Returns `Error::SerializationError` if serializing or writing to `w` fails.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_sigma_json<W: Write>(&self, w: W) -> Result<(), Error> {
+        let step = std::f64::consts::TAU / self.node_count().max(1) as f64;
+        let nodes: Vec<_> = self
+            .nodes()
+            .enumerate()
+            .map(|(i, n)| {
+                let angle = step * i as f64;
+                serde_json::json!({
+                    "id": node_id(&n.demangled),
+                    "label": n.demangled,
+                    "x": angle.cos(),
+                    "y": angle.sin(),
+                    "size": self.out_degree(&n.demangled) + 1,
+                })
+            })
+            .collect();
+
+        let edges: Vec<_> = self
+            .edges()
+            .enumerate()
+            .map(|(i, (caller, callee, count))| {
+                serde_json::json!({
+                    "id": format!("e_{i}"),
+                    "source": node_id(caller),
+                    "target": node_id(callee),
+                    "size": count,
+                })
+            })
+            .collect();
+
+        serde_json::to_writer_pretty(w, &serde_json::json!({ "nodes": nodes, "edges": edges }))
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Builds the node-link document used by D3.js force layouts: `nodes` with the degree, crate
+    /// (`group`) and unsafe flag of each function, and `links` weighted (`value`) by call count.
+    fn d3_document(&self) -> serde_json::Value {
+        let nodes: Vec<_> = self
+            .nodes()
+            .map(|n| {
+                serde_json::json!({
+                    "id": node_id(&n.demangled),
+                    "demangled": n.demangled,
+                    "group": crate_name(&n.demangled),
+                    "in_degree": self.in_degree(&n.demangled),
+                    "out_degree": self.out_degree(&n.demangled),
+                    "is_unsafe": n.is_unsafe,
+                })
+            })
+            .collect();
+
+        let links: Vec<_> = self
+            .edges()
+            .map(|(caller, callee, count)| {
+                serde_json::json!({
+                    "source": node_id(caller),
+                    "target": node_id(callee),
+                    "value": count,
+                })
+            })
+            .collect();
+
+        serde_json::json!({ "nodes": nodes, "links": links })
+    }
+
+    /// Writes the graph as a D3.js node-link JSON document. Nodes carry `id`, `demangled`,
+    /// `group` (the crate name), `in_degree`, `out_degree` and `is_unsafe`; links carry `source`,
+    /// `target` and the call count as `value`.
+    ///
+    /// # Errors
+    /// Returns `Error::SerializationError` if serializing or writing to `w` fails.
+    pub fn to_d3_json<W: Write>(&self, w: W) -> Result<(), Error> {
+        serde_json::to_writer_pretty(w, &self.d3_document())
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Writes a single HTML page rendering the graph as a draggable, zoomable and searchable
+    /// D3.js force layout. The `to_d3_json` document is embedded inline; only D3 itself is loaded
+    /// from a CDN. Hovering a node shows its name, degrees and unsafe flag.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn to_interactive_html<W: Write>(&self, mut w: W, title: &str) -> Result<(), Error> {
+        // `</` would end the inline script element early.
+        let data = self.d3_document().to_string().replace("</", "<\\/");
+        let html = INTERACTIVE_HTML_TEMPLATE
+            .replace("{{TITLE}}", &xml_escape(title))
+            .replace("{{DATA}}", &data);
+
+        Ok(w.write_all(html.as_bytes())?)
+    }
+
+    /// Writes every edge as an LLVM optimization remark of a custom `painter` pass, in the YAML
+    /// stream format of `-fsave-optimization-record`, so calls can be viewed alongside the
+    /// compiler's own remarks. Each `!Analysis` document names the caller's symbol as its
+    /// `Function`, falling back to the demangled name, and carries `Caller`, `Callee` and
+    /// `CallCount` arguments. Callers with a known location get a `DebugLoc`; debug info records
+    /// no column for functions, so its `Column` is 0.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn export_for_llvm_opt_remarks<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        for (caller_name, callee, count) in self.edges() {
+            let node = self.node(caller_name);
+            let mangled = node.and_then(|n| n.mangled.as_deref());
+            let function = yaml_escape(mangled.unwrap_or(caller_name));
+            let (caller, callee) = (yaml_escape(caller_name), yaml_escape(callee));
+            writeln!(w, "--- !Analysis")?;
+            writeln!(w, "Pass:            painter")?;
+            writeln!(w, "Name:            CallEdge")?;
+            if let Some(location) = node.and_then(|n| n.location.as_ref()) {
+                writeln!(
+                    w,
+                    "DebugLoc:        {{ File: {}, Line: {}, Column: 0 }}",
+                    yaml_escape(&location.file),
+                    location.line
+                )?;
+            }
+            writeln!(w, "Function:        {function}")?;
+            writeln!(w, "Args:")?;
+            writeln!(w, "  - Caller:          {caller}")?;
+            writeln!(w, "  - String:          ' calls '")?;
+            writeln!(w, "  - Callee:          {callee}")?;
+            writeln!(w, "  - String:          ' '")?;
+            writeln!(w, "  - CallCount:       '{count}'")?;
+            writeln!(w, "  - String:          ' times'")?;
+            writeln!(w, "...")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `call_graph_json_schema`, the schema of the JSON this graph serializes to.
+    ///
+    /// # Errors
+    /// Returns `Error::SerializationError` if serializing or writing to `w` fails.
+    #[allow(clippy::unused_self)]
+    pub fn to_json_schema<W: Write>(&self, w: W) -> Result<(), Error> {
+        serde_json::to_writer_pretty(w, &call_graph_json_schema())
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Writes the graph as a GEXF 1.3 document for import into Gephi. Nodes carry `demangled`,
+    /// `unsafe`, `in_degree`, `out_degree` and `is_extern_c` attributes and edges a `call_count`
+    /// attribute, which is also used as the edge weight.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn to_gexf_xml<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(w, r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#)?;
+        writeln!(w, r#"  <graph mode="static" defaultedgetype="directed">"#)?;
+        writeln!(w, r#"    <attributes class="node">"#)?;
+        writeln!(
+            w,
+            r#"      <attribute id="0" title="demangled" type="string"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"      <attribute id="1" title="in_degree" type="integer"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"      <attribute id="2" title="out_degree" type="integer"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"      <attribute id="3" title="unsafe" type="boolean"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"      <attribute id="4" title="is_extern_c" type="boolean"/>"#
+        )?;
+        writeln!(w, "    </attributes>")?;
+        writeln!(w, r#"    <attributes class="edge">"#)?;
+        writeln!(
+            w,
+            r#"      <attribute id="0" title="call_count" type="integer"/>"#
+        )?;
+        writeln!(w, "    </attributes>")?;
+
+        writeln!(w, "    <nodes>")?;
+        for n in self.nodes() {
+            let name = xml_escape(&n.demangled);
+            writeln!(
+                w,
+                r#"      <node id="{}" label="{name}">"#,
+                node_id(&n.demangled)
+            )?;
+            writeln!(w, "        <attvalues>")?;
+            writeln!(w, r#"          <attvalue for="0" value="{name}"/>"#)?;
+            writeln!(
+                w,
+                r#"          <attvalue for="1" value="{}"/>"#,
+                self.in_degree(&n.demangled)
+            )?;
+            writeln!(
+                w,
+                r#"          <attvalue for="2" value="{}"/>"#,
+                self.out_degree(&n.demangled)
+            )?;
+            writeln!(
+                w,
+                r#"          <attvalue for="3" value="{}"/>"#,
+                n.is_unsafe
+            )?;
+            writeln!(
+                w,
+                r#"          <attvalue for="4" value="{}"/>"#,
+                is_extern_c(&n.demangled)
+            )?;
+            writeln!(w, "        </attvalues>")?;
+            writeln!(w, "      </node>")?;
+        }
+        writeln!(w, "    </nodes>")?;
+
+        writeln!(w, "    <edges>")?;
+        for (i, (caller, callee, count)) in self.edges().enumerate() {
+            writeln!(
+                w,
+                r#"      <edge id="{i}" source="{}" target="{}" weight="{count}">"#,
+                node_id(caller),
+                node_id(callee)
+            )?;
+            writeln!(w, "        <attvalues>")?;
+            writeln!(w, r#"          <attvalue for="0" value="{count}"/>"#)?;
+            writeln!(w, "        </attvalues>")?;
+            writeln!(w, "      </edge>")?;
+        }
+        writeln!(w, "    </edges>")?;
+
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</gexf>")?;
+
+        Ok(())
+    }
+
+    /// Writes the graph as a `GraphML` document, as read by yEd, Cytoscape and `NetworkX`. Nodes
+    /// are keyed by `node_id` and carry `demangled` and `unsafe` data; edges carry `call_count`.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn to_graphml<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="demangled" for="node" attr.name="demangled" attr.type="string"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="unsafe" for="node" attr.name="unsafe" attr.type="boolean"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="call_count" for="edge" attr.name="call_count" attr.type="int"/>"#
+        )?;
+        writeln!(w, r#"  <graph id="painter" edgedefault="directed">"#)?;
+
+        for n in self.nodes() {
+            writeln!(w, r#"    <node id="{}">"#, node_id(&n.demangled))?;
+            writeln!(
+                w,
+                r#"      <data key="demangled">{}</data>"#,
+                xml_escape(&n.demangled)
+            )?;
+            writeln!(w, r#"      <data key="unsafe">{}</data>"#, n.is_unsafe)?;
+            writeln!(w, "    </node>")?;
+        }
+        for (caller, callee, count) in self.edges() {
+            writeln!(
+                w,
+                r#"    <edge source="{}" target="{}">"#,
+                node_id(caller),
+                node_id(callee)
+            )?;
+            writeln!(w, r#"      <data key="call_count">{count}</data>"#)?;
+            writeln!(w, "    </edge>")?;
+        }
+
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")?;
+
+        Ok(())
+    }
+
+    /// Writes the graph as a left to right Mermaid flowchart. Nodes are labelled with their
+    /// demangled name, unsafe functions drawn as hexagons, and edges with their call count.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn to_mermaid<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        writeln!(w, "flowchart LR")?;
+        for n in self.nodes() {
+            let (open, close) = if n.is_unsafe {
+                ("{{", "}}")
+            } else {
+                ("[", "]")
+            };
+            writeln!(
+                w,
+                r#"    n{}{open}"{}"{close}"#,
+                node_id(&n.demangled),
+                mermaid_escape(&n.demangled)
+            )?;
+        }
+        for (caller, callee, count) in self.edges() {
+            writeln!(
+                w,
+                "    n{} -->|{count}| n{}",
+                node_id(caller),
+                node_id(callee)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the graph as a `PlantUML` diagram with a rectangle per function, stereotyped
+    /// `<<unsafe>>` for unsafe functions, and arrows labelled with their call count.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn to_plantuml<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        writeln!(w, "@startuml")?;
+        for n in self.nodes() {
+            let stereotype = if n.is_unsafe { " <<unsafe>>" } else { "" };
+            writeln!(
+                w,
+                r#"rectangle "{}" as n{}{stereotype}"#,
+                plantuml_escape(&n.demangled),
+                node_id(&n.demangled)
+            )?;
+        }
+        for (caller, callee, count) in self.edges() {
+            writeln!(w, "n{} --> n{} : {count}", node_id(caller), node_id(callee))?;
+        }
+        writeln!(w, "@enduml")?;
+
+        Ok(())
+    }
+
+    /// Writes the graph in the folded stack format read by `inferno` and `flamegraph.pl`, one
+    /// `caller;callee count` line per edge weighted by its call count. A call graph does not
+    /// record deeper stacks, so each stack is two frames. Semicolons within names, as in
+    /// `[u8; 4]`, are replaced by commas since they separate frames.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn to_folded_stacks<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        for (caller, callee, count) in self.edges() {
+            writeln!(
+                w,
+                "{};{} {count}",
+                caller.replace(';', ","),
+                callee.replace(';', ",")
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the graph in the elements JSON format read by Cytoscape.js and Cytoscape Desktop.
+    /// Nodes carry `id`, `label`, `unsafe`, `in_degree` and `out_degree` data; edges carry
+    /// `source`, `target` and the call count as `count`.
+    ///
+    /// # Errors
+    /// Returns `Error::SerializationError` if serializing or writing to `w` fails.
+    pub fn to_cytoscape_json<W: Write>(&self, w: W) -> Result<(), Error> {
+        let nodes: Vec<_> = self
+            .nodes()
+            .map(|n| {
+                serde_json::json!({
+                    "data": {
+                        "id": node_id(&n.demangled),
+                        "label": n.demangled,
+                        "unsafe": n.is_unsafe,
+                        "in_degree": self.in_degree(&n.demangled),
+                        "out_degree": self.out_degree(&n.demangled),
+                    }
+                })
+            })
+            .collect();
+
+        let edges: Vec<_> = self
+            .edges()
+            .enumerate()
+            .map(|(i, (caller, callee, count))| {
+                serde_json::json!({
+                    "data": {
+                        "id": format!("e_{i}"),
+                        "source": node_id(caller),
+                        "target": node_id(callee),
+                        "count": count,
+                    }
+                })
+            })
+            .collect();
+
+        serde_json::to_writer_pretty(
+            w,
+            &serde_json::json!({ "elements": { "nodes": nodes, "edges": edges } }),
+        )
+        .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Assigns every node an RGB color by `metric`: numeric metrics are scaled onto a green,
+    /// yellow, red gradient relative to the largest value, and crates get distinct colors from a
+    /// categorical palette in name order. Keyed by demangled name, for use with
+    /// `to_hierarchical_dot`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn assign_colors_by_metric(&self, metric: ColorMetric) -> HashMap<String, [u8; 3]> {
+        let value = |name: &str| -> usize {
+            match metric {
+                ColorMetric::ByOutDegree => self.out_degree(name),
+                ColorMetric::ByInDegree => self.in_degree(name),
+                ColorMetric::ByCallCount => self
+                    .callers(name)
+                    .filter_map(|caller| self.call_count(caller, name))
+                    .map(|count| count as usize)
+                    .sum(),
+                ColorMetric::ByCrateName => 0,
+                ColorMetric::ByUnsafe => usize::from(self.node(name).is_some_and(|n| n.is_unsafe)),
+            }
+        };
+
+        if metric == ColorMetric::ByCrateName {
+            let crates: std::collections::BTreeSet<_> =
+                self.nodes().map(|n| crate_name(&n.demangled)).collect();
+            let color_of: HashMap<_, _> = crates
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| (name, CRATE_PALETTE[i % CRATE_PALETTE.len()]))
+                .collect();
+
+            return self
+                .nodes()
+                .map(|n| (n.demangled.clone(), color_of[crate_name(&n.demangled)]))
+                .collect();
+        }
+
+        let values: Vec<_> = self
+            .nodes()
+            .map(|n| (n.demangled.as_str(), value(&n.demangled)))
+            .collect();
+        let max = values.iter().map(|(_, v)| *v).max().unwrap_or(0).max(1) as f64;
+
+        values
+            .into_iter()
+            .map(|(name, v)| (name.to_owned(), gradient(v as f64 / max)))
+            .collect()
+    }
+
+    /// Renders the graph as a Graphviz DOT document with every strongly connected component of two
+    /// or more functions drawn as a `cluster_SCC_N` subgraph, labelled with its first member and
+    /// size; singleton components are plain nodes. Calls within a component are drawn inside its
+    /// cluster, and calls between components connect the functions on the cluster borders.
+    /// Nodes listed in `colors`, e.g. from `assign_colors_by_metric`, are filled with that color.
+    #[must_use]
+    pub fn to_hierarchical_dot(&self, colors: Option<&HashMap<String, [u8; 3]>>) -> String {
+        let components = self.strongly_connected_components();
+        let component_of: HashMap<&str, usize> = components
+            .iter()
+            .enumerate()
+            .flat_map(|(i, c)| c.iter().map(move |n| (n.as_str(), i)))
+            .collect();
+
+        let node = |name: &str| {
+            let fill = colors
+                .and_then(|c| c.get(name))
+                .map(|[r, g, b]| format!(", style=filled, fillcolor=\"#{r:02x}{g:02x}{b:02x}\""))
+                .unwrap_or_default();
+            format!(
+                "\"{}\" [label=\"{}\"{fill}];",
+                node_id(name),
+                dot_escape(name)
+            )
+        };
+        let edge = |caller: &str, callee: &str| {
+            format!("\"{}\" -> \"{}\";", node_id(caller), node_id(callee))
+        };
+
+        let mut lines = vec!["digraph painter {".to_owned()];
+        let mut cluster = 0;
+        for (i, component) in components.iter().enumerate() {
+            if component.len() < 2 {
+                lines.push(format!("  {}", node(&component[0])));
+                continue;
+            }
+
+            lines.push(format!("  subgraph cluster_SCC_{cluster} {{"));
+            lines.push(format!(
+                "    label=\"{}; {} functions\";",
+                dot_escape(&component[0]),
+                component.len()
+            ));
+            lines.extend(component.iter().map(|name| format!("    {}", node(name))));
+            lines.extend(
+                self.edges()
+                    .filter(|(a, b, _)| component_of[a] == i && component_of[b] == i)
+                    .map(|(caller, callee, _)| format!("    {}", edge(caller, callee))),
+            );
+            lines.push("  }".to_owned());
+            cluster += 1;
+        }
+
+        lines.extend(
+            self.edges()
+                .filter(|(caller, callee, _)| component_of[caller] != component_of[callee])
+                .map(|(caller, callee, _)| format!("  {}", edge(caller, callee))),
+        );
+        lines.push("}\n".to_owned());
+
+        lines.join("\n")
+    }
+
+    /// Writes a human readable summary of the graph: the top 20 functions by out-degree and by
+    /// in-degree, the instability of each crate as computed by `compute_crate_instability_metric`,
+    /// a summary of the cycles found by `find_cycles`, followed by every call to an unsafe
+    /// function and to an extern C function, see `is_extern_c`.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if writing to `w` fails.
+    pub fn to_text_report<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        const TOP: usize = 20;
+
+        let sections: [ReportSection<usize>; 2] = [
+            ("Nodes by out-degree", Self::out_degree),
+            ("Nodes by in-degree", Self::in_degree),
+        ];
+        for (title, degree) in sections {
+            let mut rows: Vec<_> = self
+                .nodes()
+                .map(|n| (n.demangled.as_str(), degree(self, &n.demangled)))
+                .collect();
+            rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            rows.truncate(TOP);
+
+            let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+            writeln!(w, "{title} (top {TOP})")?;
+            for (name, degree) in rows {
+                writeln!(w, "  {name:<width$}  {degree:>6}")?;
+            }
+            writeln!(w)?;
+        }
+
+        let mut instability: Vec<_> = compute_crate_instability_metric(self).into_iter().collect();
+        if !instability.is_empty() {
+            instability.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+            let width = instability
+                .iter()
+                .map(|(name, _)| name.len())
+                .max()
+                .unwrap_or(0);
+            writeln!(w, "Crate instability")?;
+            for (name, score) in instability {
+                writeln!(w, "  {name:<width$}  {score:>6.2}")?;
+            }
+            writeln!(w)?;
+        }
+
+        let cycles = self.find_cycles();
+        match cycles.iter().max_by_key(|c| c.len()) {
+            Some(largest) => {
+                writeln!(
+                    w,
+                    "Cycle summary ({} cycles, largest: {} functions)",
+                    cycles.len(),
+                    largest.len()
+                )?;
+                for name in largest {
+                    writeln!(w, "  {name}")?;
+                }
+            }
+            None => writeln!(w, "Cycle summary (0 cycles)")?,
+        }
+
+        let call_sections: [ReportSection<bool>; 2] = [
+            ("Unsafe callsites", |graph, callee| {
+                graph.node(callee).is_some_and(|n| n.is_unsafe)
+            }),
+            ("Extern C calls", |_, callee| is_extern_c(callee)),
+        ];
+        for (title, matches) in call_sections {
+            let calls: Vec<_> = self
+                .edges()
+                .filter(|(_, callee, _)| matches(self, callee))
+                .collect();
+            let total: u64 = calls.iter().map(|(_, _, count)| u64::from(*count)).sum();
+
+            let width = calls
+                .iter()
+                .map(|(caller, ..)| caller.len())
+                .max()
+                .unwrap_or(0);
+            writeln!(w)?;
+            writeln!(w, "{title} ({total} total)")?;
+            for (caller, callee, count) in calls {
+                writeln!(w, "  {caller:<width$}  {callee}  {count:>6}")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the graph in the compact `MessagePack` encoding of its serde representation.
+    ///
+    /// # Errors
+    /// Returns `Error::SerializationError` if encoding or writing to `w` fails.
+    #[cfg(feature = "msgpack")]
+    pub fn export_to_msgpack<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        rmp_serde::encode::write_named(&mut w, self)
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Reads a graph written by `export_to_msgpack`.
+    ///
+    /// # Errors
+    /// Returns `Error::SerializationError` if reading or decoding fails.
+    #[cfg(feature = "msgpack")]
+    pub fn import_from_msgpack<R: std::io::Read>(r: R) -> Result<CallGraph, Error> {
+        rmp_serde::from_read(r).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Writes the graph as an Excel workbook with a `Nodes` sheet (demangled and mangled names,
+    /// unsafe flag, degrees and crate of each function) and an `Edges` sheet (caller, callee and
+    /// call count), each with an auto-filter over its columns. The rows of unsafe functions are
+    /// highlighted in red.
+    ///
+    /// # Errors
+    /// Returns `Error::SerializationError` if building or writing the workbook fails.
+    #[cfg(feature = "excel")]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn to_excel<W: Write + std::io::Seek + Send>(&self, w: W) -> Result<(), Error> {
+        use rust_xlsxwriter::{Color, ConditionalFormatFormula, Format, Workbook, XlsxError};
+
+        fn row(i: usize) -> Result<u32, XlsxError> {
+            u32::try_from(i + 1).map_err(|_| XlsxError::RowColumnLimitError)
+        }
+
+        let write = || -> Result<(), XlsxError> {
+            let mut workbook = Workbook::new();
+
+            let nodes = workbook.add_worksheet().set_name("Nodes")?;
+            let headers = [
+                "Demangled",
+                "Mangled",
+                "Unsafe",
+                "InDegree",
+                "OutDegree",
+                "CrateName",
+            ];
+            for (col, header) in headers.into_iter().enumerate() {
+                nodes.write_string(0, col as u16, header)?;
+            }
+            for (i, n) in self.nodes().enumerate() {
+                let row = row(i)?;
+                nodes.write_string(row, 0, &n.demangled)?;
+                if let Some(mangled) = &n.mangled {
+                    nodes.write_string(row, 1, mangled)?;
+                }
+                nodes.write_boolean(row, 2, n.is_unsafe)?;
+                nodes.write_number(row, 3, self.in_degree(&n.demangled) as f64)?;
+                nodes.write_number(row, 4, self.out_degree(&n.demangled) as f64)?;
+                nodes.write_string(row, 5, crate_name(&n.demangled))?;
+            }
+            let last_row = row(self.node_count().saturating_sub(1))?;
+            let last_col = headers.len() as u16 - 1;
+            nodes.autofilter(0, 0, last_row, last_col)?;
+            // Relative to the first row, so each row tests its own Unsafe cell.
+            let unsafe_rows = ConditionalFormatFormula::new()
+                .set_rule("=$C2=TRUE")
+                .set_format(
+                    Format::new()
+                        .set_font_color(Color::RGB(0x009C_0006))
+                        .set_background_color(Color::RGB(0x00FF_C7CE)),
+                );
+            nodes.add_conditional_format(1, 0, last_row, last_col, &unsafe_rows)?;
+
+            let edges = workbook.add_worksheet().set_name("Edges")?;
+            for (col, header) in ["Caller", "Callee", "CallCount"].into_iter().enumerate() {
+                edges.write_string(0, col as u16, header)?;
+            }
+            for (i, (caller, callee, count)) in self.edges().enumerate() {
+                let row = row(i)?;
+                edges.write_string(row, 0, caller)?;
+                edges.write_string(row, 1, callee)?;
+                edges.write_number(row, 2, f64::from(count))?;
+            }
+            edges.autofilter(0, 0, row(self.edge_count().saturating_sub(1))?, 2)?;
+
+            workbook.save_to_writer(w)
+        };
+
+        write().map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+/// JSON Schema (draft 7) of the `serde` JSON representation of `CallGraph`, as written by
+/// `OutputFormat::Json`. Node fields are all optional, as they default when deserializing, but
+/// unknown fields are rejected so the schema cannot fall behind `FunctionNode`.
+#[must_use]
+pub fn call_graph_json_schema() -> serde_json::Value {
+    let boolean = || serde_json::json!({ "type": "boolean" });
+    let strings = || serde_json::json!({ "type": "array", "items": { "type": "string" } });
+    let unsigned =
+        |format: &str| serde_json::json!({ "type": "integer", "minimum": 0, "format": format });
+
+    serde_json::json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "CallGraph",
+        "type": "object",
+        "required": ["nodes", "edges"],
+        "additionalProperties": false,
+        "properties": {
+            "nodes": { "type": "array", "items": { "$ref": "#/definitions/FunctionNode" } },
+            "edges": { "type": "array", "items": { "$ref": "#/definitions/Edge" } },
+        },
+        "definitions": {
+            "FunctionNode": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "demangled": { "type": "string" },
+                    "is_public_api": boolean(),
+                    "is_sanitizer_hook": boolean(),
+                    "is_synthetic": boolean(),
+                    "has_indirect_calls": boolean(),
+                    "mutates_globals": boolean(),
+                    "is_async": boolean(),
+                    "execution_count": {
+                        "type": ["integer", "null"],
+                        "minimum": 0,
+                        "format": "uint64",
+                    },
+                    "is_coverage_counter": boolean(),
+                    "location": {
+                        "oneOf": [{ "$ref": "#/definitions/SourceLocation" }, { "type": "null" }],
+                    },
+                    "clippy_warnings": strings(),
+                    "is_global_ctor": boolean(),
+                    "is_panic_free": boolean(),
+                    "parameter_types": strings(),
+                    "allocation_site_count": unsigned("uint32"),
+                    "in_vtable": boolean(),
+                    "is_approximate": boolean(),
+                    "is_unsafe": boolean(),
+                    "mangled": { "type": ["string", "null"] },
+                },
+            },
+            "SourceLocation": {
+                "type": "object",
+                "required": ["file", "line"],
+                "additionalProperties": false,
+                "properties": {
+                    "file": { "type": "string" },
+                    "line": unsigned("uint32"),
+                },
+            },
+            "Edge": {
+                "type": "object",
+                "required": ["caller", "callee", "count"],
+                "additionalProperties": false,
+                "properties": {
+                    "caller": { "type": "string" },
+                    "callee": { "type": "string" },
+                    "count": unsigned("uint32"),
+                    "synthetic": boolean(),
+                },
+            },
+        },
+    })
+}
+
+/// Crates shipped with the toolchain's sysroot rather than published on crates.io.
+const SYSROOT_CRATES: &[&str] = &[
+    "alloc",
+    "compiler_builtins",
+    "core",
+    "panic_abort",
+    "panic_unwind",
+    "proc_macro",
+    "std",
+    "std_detect",
+    "test",
+    "unwind",
+];
+
+/// Builds a `CycloneDX` 1.4 SBOM for the crate analyzed into `graph`. The crate itself is the
+/// top level component; every other crate whose functions appear in the graph, as determined by
+/// `crate_name`, is listed as a component with a link to its crates.io page. Sysroot crates and
+/// names which are not crate identifiers, such as the `NONE` of unqualified symbols, are left out.
+/// Versions of the dependencies are not known from the graph and are omitted.
+#[must_use]
+pub fn generate_sbom_json(graph: &CallGraph, crate_coord: &CrateEntry) -> serde_json::Value {
+    let is_identifier = |krate: &str| {
+        krate.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && krate.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+
+    let own_name = crate_coord.name().replace('-', "_");
+    let crates: std::collections::BTreeSet<&str> = graph
+        .nodes()
+        .map(|n| crate_name(&n.demangled))
+        .filter(|krate| *krate != "NONE" && *krate != own_name)
+        .filter(|krate| is_identifier(krate) && !SYSROOT_CRATES.contains(krate))
+        .collect();
+
+    let components: Vec<_> = crates
+        .into_iter()
+        .map(|krate| {
+            serde_json::json!({
+                "type": "library",
+                "bom-ref": format!("pkg:cargo/{krate}"),
+                "name": krate,
+                "purl": format!("pkg:cargo/{krate}"),
+                "externalReferences": [{
+                    "type": "distribution",
+                    "url": format!("https://crates.io/crates/{krate}"),
+                }],
+            })
+        })
+        .collect();
+
+    let purl = format!("pkg:cargo/{}@{}", crate_coord.name(), crate_coord.version());
+    serde_json::json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.4",
+        "version": 1,
+        "metadata": {
+            "tools": [{ "name": "painter", "version": env!("CARGO_PKG_VERSION") }],
+            "component": {
+                "type": "library",
+                "bom-ref": purl,
+                "name": crate_coord.name(),
+                "version": crate_coord.version(),
+                "purl": purl,
+                "externalReferences": [{
+                    "type": "distribution",
+                    "url": format!(
+                        "https://crates.io/crates/{}/{}",
+                        crate_coord.name(),
+                        crate_coord.version()
+                    ),
+                }],
+            },
+        },
+        "components": components,
+    })
+}
+
+/// Writes `graph` to `w` in the given format, dispatching to the matching `CallGraph` method.
+///
+/// # Errors
+/// Returns the error of the underlying export method.
+pub fn write_call_graph<W: Write>(
+    graph: &CallGraph,
+    format: OutputFormat,
+    mut w: W,
+) -> Result<(), Error> {
+    match format {
+        OutputFormat::Text => graph.to_text_report(w),
+        OutputFormat::Json => serde_json::to_writer_pretty(w, graph)
+            .map_err(|e| Error::SerializationError(e.to_string())),
+        OutputFormat::Jgf => graph.to_jgf_json(w),
+        OutputFormat::Gexf => graph.to_gexf_xml(w),
+        OutputFormat::Dot => Ok(w.write_all(graph.to_hierarchical_dot(None).as_bytes())?),
+        OutputFormat::Csv => graph.to_csv(w),
+        OutputFormat::Tsv => graph.to_tsv(w),
+        OutputFormat::GraphMl => graph.to_graphml(w),
+        OutputFormat::Mermaid => graph.to_mermaid(w),
+        OutputFormat::PlantUml => graph.to_plantuml(w),
+        OutputFormat::Cypher => graph.export_to_neo4j_cypher(w, &Neo4jOptions::default()),
+        OutputFormat::Gremlin => graph.to_gremlin_groovy(w),
+        OutputFormat::SigmaJson => graph.to_sigma_json(w),
+        OutputFormat::D3Json => graph.to_d3_json(w),
+        OutputFormat::CytoscapeJson => graph.to_cytoscape_json(w),
+        OutputFormat::FlamegraphData => graph.to_folded_stacks(w),
+        OutputFormat::Html => graph.to_interactive_html(w, "painter call graph"),
+        #[cfg(feature = "excel")]
+        OutputFormat::Xlsx => {
+            // Workbooks are zip archives, which need a seekable writer.
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            graph.to_excel(&mut buffer)?;
+            Ok(w.write_all(buffer.get_ref())?)
+        }
+        #[cfg(feature = "msgpack")]
+        OutputFormat::MsgPack => graph.export_to_msgpack(w),
+    }
+}
+
+/// Writes `graph` to stdout in the given format, see `write_call_graph`.
+///
+/// # Errors
+/// Returns the error of the underlying export method.
+pub fn write_call_graph_to_stdout(graph: &CallGraph, format: OutputFormat) -> Result<(), Error> {
+    write_call_graph(graph, format, std::io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "msgpack")]
+    extern crate test;
+
+    /// Small graph with an unsafe function and a name needing escaping.
+    fn fixture() -> CallGraph {
+        let mut graph: CallGraph = [
+            ("app::main", "app::run"),
+            ("app::run", "app::parse<\"quoted\">"),
+            ("app::run", "memcpy"),
+        ]
+        .into_iter()
+        .collect();
+        let run = graph.node_mut("app::run").unwrap();
+        run.is_unsafe = true;
+        run.mangled = Some("_ZN3app3run17h0123456789abcdefE".to_owned());
+        graph
+    }
+
+    #[test]
+    fn cypher_script_is_well_formed() {
+        let mut out = Vec::new();
+        let opts = Neo4jOptions {
+            batch_size: 2,
+            ..Neo4jOptions::default()
+        };
+        fixture().export_to_neo4j_cypher(&mut out, &opts).unwrap();
+        let script = String::from_utf8(out).unwrap();
+
+        let statement = regex::Regex::new(
+            r#"^(:use \w+|UNWIND \[(\{[a-z]+: ("([^"\\]|\\.)*"|\w+)(, [a-z]+: ("([^"\\]|\\.)*"|\w+))*\}(, )?)+\] AS row (MERGE|MATCH) .*);$"#,
+        )
+        .unwrap();
+        for line in script.lines() {
+            assert!(statement.is_match(line), "malformed statement: {line}");
+        }
+        // 4 nodes and 3 edges in batches of 2
+        assert_eq!(script.lines().count(), 1 + 2 + 2);
+        assert!(script.contains(r#"{name: "app::run", unsafe: true}"#));
+    }
+
+    #[test]
+    fn gremlin_script_is_well_formed() {
+        let mut graph = fixture();
+        graph.add_edge("app::run", "app::it's");
+        let mut out = Vec::new();
+        graph.to_gremlin_groovy(&mut out).unwrap();
+        let script = String::from_utf8(out).unwrap();
+
+        let string = r"'(?:[^'\\]|\\.)*'";
+        let vertex = format!(
+            r"addV\('Function'\)\.property\(id, {string}\)\.property\('name', {string}\)\.property\('unsafe', (?:true|false)\)"
+        );
+        let edge = format!(
+            r"V\({string}\)\.addE\('calls'\)\.to\(__\.V\({string}\)\)\.property\('count', \d+\)"
+        );
+        let line = regex::Regex::new(&format!(
+            r"^g\.(?:{vertex}|{edge})(?:\.(?:{vertex}|{edge}))*\.iterate\(\)$"
+        ))
+        .unwrap();
+
+        let lines: Vec<_> = script.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for l in &lines {
+            assert!(line.is_match(l), "malformed statement: {l}");
+        }
+        assert!(script.contains("'app::it\\'s'"));
+        assert_eq!(script.matches(".property('unsafe', true)").count(), 1);
+    }
+
+    #[test]
+    fn unsafe_functions_are_colored_red() {
+        let colors = fixture().assign_colors_by_metric(ColorMetric::ByUnsafe);
+        assert_eq!(colors["app::run"], [255, 0, 0]);
+        assert_eq!(colors["app::main"], [0, 255, 0]);
+        assert_eq!(colors["memcpy"], [0, 255, 0]);
+    }
+
+    #[test]
+    fn tsv_round_trip() {
+        let mut graph = fixture();
+        graph.add_edge_with_count("app::main", "app::run", 2);
+        let mut out = Vec::new();
+        graph.to_tsv(&mut out).unwrap();
+        let decoded = CallGraph::from_tsv(out.as_slice()).unwrap();
+
+        assert!(decoded.edges().eq(graph.edges()));
+        assert!(CallGraph::from_tsv("a\tb\n".as_bytes()).is_err());
+        assert!(CallGraph::from_tsv("a\tb\tmany\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn sigma_json_has_sized_nodes_and_edges() {
+        let mut graph = fixture();
+        graph.add_edge_with_count("app::run", "memcpy", 4);
+        let mut out = Vec::new();
+        graph.to_sigma_json(&mut out).unwrap();
+        let doc: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        let nodes = doc["nodes"].as_array().unwrap();
+        assert_eq!(nodes.len(), graph.node_count());
+        for (node, function) in nodes.iter().zip(graph.nodes()) {
+            assert_eq!(node["id"], node_id(&function.demangled));
+            assert_eq!(node["label"], function.demangled);
+            assert!(node["x"].is_f64() && node["y"].is_f64());
+            assert_eq!(node["size"], graph.out_degree(&function.demangled) + 1);
+        }
+
+        let edges = doc["edges"].as_array().unwrap();
+        assert_eq!(edges.len(), graph.edge_count());
+        for (i, (edge, (caller, callee, count))) in edges.iter().zip(graph.edges()).enumerate() {
+            assert_eq!(edge["id"], format!("e_{i}"));
+            assert_eq!(edge["source"], node_id(caller));
+            assert_eq!(edge["target"], node_id(callee));
+            assert_eq!(edge["size"], count);
+        }
+        assert!(edges.iter().any(|edge| edge["size"] == 5));
+    }
+
+    #[test]
+    fn folded_stacks_have_a_line_per_edge() {
+        let mut graph = fixture();
+        graph.add_edge_with_count("app::run", "memcpy", 2);
+        graph.add_edge("app::main", "app::fill<[u8; 4]>");
+        let mut out = Vec::new();
+        write_call_graph(&graph, OutputFormat::FlamegraphData, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "app::main;app::fill<[u8, 4]> 1\n\
+             app::main;app::run 1\n\
+             app::run;app::parse<\"quoted\"> 1\n\
+             app::run;memcpy 3\n"
+        );
+    }
+
+    #[test]
+    fn text_table_is_an_alias_of_tsv() {
+        use clap::ValueEnum;
+
+        assert_eq!(
+            OutputFormat::from_str("text-table", false),
+            Ok(OutputFormat::Tsv)
+        );
+    }
+
+    #[test]
+    fn json_matches_schema() {
+        let mut graph = fixture();
+        graph.add_synthetic_edge("app::main", "memcpy").unwrap();
+        let node = graph.node_mut("app::main").unwrap();
+        node.execution_count = Some(3);
+        node.location = Some(crate::graph::SourceLocation {
+            file: "src/main.rs".to_owned(),
+            line: 1,
+        });
+
+        let schema = jsonschema::JSONSchema::compile(&call_graph_json_schema()).unwrap();
+        let json = serde_json::to_value(&graph).unwrap();
+        if let Err(errors) = schema.validate(&json) {
+            let errors: Vec<_> = errors
+                .map(|e| format!("{}: {e}", e.instance_path))
+                .collect();
+            panic!("graph does not match its schema: {errors:?}");
+        }
+
+        let decoded: CallGraph = serde_json::from_value(json.clone()).unwrap();
+        assert!(decoded.nodes().eq(graph.nodes()));
+        assert!(decoded.edges().eq(graph.edges()));
+
+        let mut unknown = json;
+        unknown["nodes"][0]["is_unknown"] = serde_json::Value::Bool(true);
+        assert!(!schema.is_valid(&unknown));
+    }
+
+    #[test]
+    fn sbom_matches_cyclonedx_schema() {
+        let graph: CallGraph = [
+            ("app::main", "app::run"),
+            ("app::run", "<app::Config as core::fmt::Debug>::fmt"),
+            ("app::run", "serde_json::from_str"),
+            ("serde_json::from_str", "alloc::alloc::exchange_malloc"),
+            ("serde_json::from_str", "std::io::stdio::_print"),
+            ("serde_json::from_str", "core::panicking::panic"),
+            ("app::run", "memcpy"),
+        ]
+        .into_iter()
+        .collect();
+        let sbom = generate_sbom_json(&graph, &CrateEntry::new("app-0.1.0".to_owned()).unwrap());
+
+        let components: Vec<_> = sbom["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(components, ["serde_json"]);
+        assert_eq!(sbom["metadata"]["component"]["purl"], "pkg:cargo/app@0.1.0");
+
+        let document = |json: &str| serde_json::from_str::<serde_json::Value>(json).unwrap();
+        let spdx = document(include_str!("../test_data/cyclonedx/spdx.schema.json"));
+        let jsf = document(include_str!("../test_data/cyclonedx/jsf-0.82.schema.json"));
+        let schema = jsonschema::JSONSchema::options()
+            .with_document(spdx["$id"].as_str().unwrap().to_owned(), spdx)
+            .with_document(jsf["$id"].as_str().unwrap().to_owned(), jsf)
+            .compile(&document(include_str!(
+                "../test_data/cyclonedx/bom-1.4.schema.json"
+            )))
+            .unwrap();
+        if let Err(errors) = schema.validate(&sbom) {
+            let errors: Vec<_> = errors
+                .map(|e| format!("{}: {e}", e.instance_path))
+                .collect();
+            panic!("SBOM does not match the CycloneDX 1.4 schema: {errors:?}");
+        }
+
+        let mut invalid = sbom.clone();
+        invalid["components"][0]["type"] = "crate".into();
+        assert!(!schema.is_valid(&invalid));
+    }
+
+    #[test]
+    fn opt_remarks_are_analysis_documents() {
+        use serde::Deserialize;
+
+        let mut graph = fixture();
+        graph.node_mut("app::run").unwrap().location = Some(crate::graph::SourceLocation {
+            file: "src/run.rs".to_owned(),
+            line: 12,
+        });
+        let mut out = Vec::new();
+        graph.export_for_llvm_opt_remarks(&mut out).unwrap();
+        let yaml = String::from_utf8(out).unwrap();
+
+        let remarks: Vec<_> = serde_yaml::Deserializer::from_str(&yaml)
+            .map(|doc| match serde_yaml::Value::deserialize(doc).unwrap() {
+                serde_yaml::Value::Tagged(remark) => {
+                    assert_eq!(remark.tag, "!Analysis");
+                    remark.value
+                }
+                other => panic!("remark without a tag: {other:?}"),
+            })
+            .collect();
+        assert_eq!(remarks.len(), graph.edge_count());
+
+        for (remark, (caller, callee, count)) in remarks.iter().zip(graph.edges()) {
+            let node = graph.node(caller).unwrap();
+            assert_eq!(remark["Pass"], "painter");
+            assert_eq!(remark["Name"], "CallEdge");
+            assert_eq!(
+                remark["Function"],
+                node.mangled.as_deref().unwrap_or(caller)
+            );
+
+            let args = remark["Args"].as_sequence().unwrap();
+            assert_eq!(args.len(), 6);
+            assert_eq!(args[0]["Caller"], caller);
+            assert_eq!(args[2]["Callee"], callee);
+            assert_eq!(args[4]["CallCount"], count.to_string().as_str());
+
+            match &node.location {
+                Some(location) => {
+                    let debugloc = &remark["DebugLoc"];
+                    assert_eq!(debugloc["File"], location.file.as_str());
+                    assert_eq!(debugloc["Line"].as_u64(), Some(u64::from(location.line)));
+                    assert_eq!(debugloc["Column"].as_u64(), Some(0));
+                }
+                None => assert!(remark.get("DebugLoc").is_none()),
+            }
+        }
+        assert_eq!(
+            remarks[1]["Function"], "_ZN3app3run17h0123456789abcdefE",
+            "app::run calls by its symbol"
+        );
+    }
+
+    #[test]
+    fn text_report_has_every_section() {
+        let mut out = Vec::new();
+        fixture().to_text_report(&mut out).unwrap();
+        let report = String::from_utf8(out).unwrap();
+
+        for header in [
+            "Nodes by out-degree (top 20)",
+            "Nodes by in-degree (top 20)",
+            "Cycle summary (0 cycles)",
+            "Unsafe callsites (1 total)",
+            "Extern C calls (1 total)",
+        ] {
+            assert!(
+                report.lines().any(|l| l == header),
+                "missing section: {header}"
+            );
+        }
+    }
+
+    /// Number of `element` elements in the XML document `xml`, failing on malformed XML.
+    fn count_xml_elements(xml: &[u8], element: &str) -> usize {
+        use quick_xml::events::Event;
+
+        let mut reader = quick_xml::Reader::from_reader(xml);
+        let mut buf = Vec::new();
+        let mut count = 0;
+        loop {
+            match reader.read_event_into(&mut buf).unwrap() {
+                Event::Start(e) | Event::Empty(e) if e.name().as_ref() == element.as_bytes() => {
+                    count += 1;
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        count
+    }
+
+    #[test]
+    fn gexf_lists_every_node() {
+        let graph = fixture();
+        let mut out = Vec::new();
+        graph.to_gexf_xml(&mut out).unwrap();
+
+        assert_eq!(count_xml_elements(&out, "node"), graph.nodes().count());
+    }
+
+    #[test]
+    fn graphml_lists_every_node_and_edge() {
+        let graph = fixture();
+        let mut out = Vec::new();
+        graph.to_graphml(&mut out).unwrap();
+
+        assert_eq!(count_xml_elements(&out, "node"), graph.node_count());
+        assert_eq!(count_xml_elements(&out, "edge"), graph.edge_count());
+    }
+
+    #[test]
+    fn csv_quotes_fields() {
+        let mut graph = fixture();
+        graph.add_edge("app::run", "<app::Foo as core::ops::Fn<(u8, u8)>>::call");
+        let mut out = Vec::new();
+        graph.to_csv(&mut out).unwrap();
+
+        let rows: Vec<_> = csv::Reader::from_reader(out.as_slice())
+            .records()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows.len(), graph.edge_count());
+        assert!(rows
+            .iter()
+            .zip(graph.edges())
+            .all(|(row, (caller, callee, count))| {
+                row == vec![caller, callee, &count.to_string()]
+            }));
+    }
+
+    #[cfg(feature = "excel")]
+    #[test]
+    fn excel_sheets_have_a_cell_per_column() {
+        use calamine::Reader;
+
+        let graph = fixture();
+        let path = std::env::temp_dir().join(format!("painter-{}-graph.xlsx", std::process::id()));
+        graph
+            .to_excel(std::fs::File::create(&path).unwrap())
+            .unwrap();
+
+        let mut workbook: calamine::Xlsx<_> = calamine::open_workbook(&path).unwrap();
+        let nodes = workbook.worksheet_range("Nodes").unwrap();
+        let edges = workbook.worksheet_range("Edges").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Only app::run has a mangled name, the other Mangled cells are left empty.
+        let mangled = graph.nodes().filter(|n| n.mangled.is_some()).count();
+        assert_eq!(
+            nodes.used_cells().count(),
+            6 * (graph.node_count() + 1) - (graph.node_count() - mangled)
+        );
+        assert_eq!(edges.used_cells().count(), 3 * (graph.edge_count() + 1));
+        assert_eq!(
+            nodes.get_value((3, 1)),
+            Some(&calamine::Data::String(
+                "_ZN3app3run17h0123456789abcdefE".to_owned()
+            ))
+        );
+        assert_eq!(
+            nodes.get_value((3, 2)),
+            Some(&calamine::Data::Bool(true)),
+            "app::run is unsafe"
+        );
+    }
+
+    /// Chain of `len` functions spread over a handful of crates, each calling a shared helper.
+    #[cfg(feature = "msgpack")]
+    fn large_fixture(len: usize) -> CallGraph {
+        let mut graph = CallGraph::default();
+        for i in 1..len {
+            let caller = format!("crate{}::module::function_{}", (i - 1) % 8, i - 1);
+            graph.add_edge(&caller, format!("crate{}::module::function_{i}", i % 8));
+            graph.add_edge(&caller, "core::panicking::panic");
+        }
+        graph
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_round_trip() {
+        let graph = fixture();
+        let mut out = Vec::new();
+        graph.export_to_msgpack(&mut out).unwrap();
+        let decoded = CallGraph::import_from_msgpack(out.as_slice()).unwrap();
+
+        assert!(decoded.nodes().eq(graph.nodes()));
+        assert!(decoded.edges().eq(graph.edges()));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_is_smaller_than_json() {
+        let graph = large_fixture(1000);
+        let mut msgpack = Vec::new();
+        graph.export_to_msgpack(&mut msgpack).unwrap();
+        let json = serde_json::to_vec(&graph).unwrap();
+
+        assert!(msgpack.len() < json.len());
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[bench]
+    fn bench_encode_msgpack(b: &mut test::Bencher) {
+        let graph = large_fixture(1000);
+        let mut out = Vec::new();
+        graph.export_to_msgpack(&mut out).unwrap();
+        b.bytes = out.len() as u64;
+        b.iter(|| {
+            out.clear();
+            graph.export_to_msgpack(&mut out).unwrap();
+        });
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[bench]
+    fn bench_encode_json(b: &mut test::Bencher) {
+        let graph = large_fixture(1000);
+        let mut out = serde_json::to_vec(&graph).unwrap();
+        b.bytes = out.len() as u64;
+        b.iter(|| {
+            out.clear();
+            serde_json::to_writer(&mut out, &graph).unwrap();
+        });
+    }
+}
@@ -0,0 +1,2436 @@
+#![allow(clippy::module_name_repetitions)]
+
+use crate::{analysis::AnalysisConfig, Error};
+use regex::Regex;
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet},
+    path::Path,
+};
+
+/// Symbol prefixes of the runtime hooks inserted by sanitizer instrumentation.
+const SANITIZER_PREFIXES: &[&str] = &["__asan_", "__msan_", "__tsan_", "__lsan_", "__sanitizer_"];
+
+/// Symbol prefix of the LLVM profiling runtime used by coverage instrumentation.
+const COVERAGE_PREFIX: &str = "__llvm_profile_";
+
+/// Prefixes of the demangled names of the functions behind `panic!`, `unwrap` and `expect`.
+const PANIC_FUNCTIONS: &[&str] = &[
+    "core::panicking::",
+    "std::panicking::begin_panic",
+    "core::option::expect_failed",
+    "core::option::unwrap_failed",
+    "core::result::unwrap_failed",
+];
+
+/// Returns true if `demangled` names one of the standard library panic entry points. These are
+/// dropped by `analysis::BLOCKED_STRINGS` unless `AnalysisConfig::include_panic_sites` is set.
+#[must_use]
+pub fn is_panic_function(demangled: &str) -> bool {
+    PANIC_FUNCTIONS.iter().any(|p| demangled.starts_with(p))
+}
+
+/// Rounds of power iteration used by `CallGraph::compute_hub_nodes` and
+/// `CallGraph::compute_authority_nodes`.
+const HITS_ITERATIONS: u32 = 20;
+
+/// Path segments rustc uses when lowering async functions and closures into state machines.
+const ASYNC_MARKERS: &[&str] = &[
+    "::{{closure}}",
+    "::{closure#",
+    "::{{generator}}",
+    "::{async_fn",
+];
+
+/// If `demangled` names a compiler generated async state machine, returns the name of the logical
+/// function it belongs to, e.g. `my_crate::fetch` for `my_crate::fetch::{{closure}}`.
+#[must_use]
+pub fn async_function_name(demangled: &str) -> Option<&str> {
+    ASYNC_MARKERS
+        .iter()
+        .filter_map(|m| demangled.find(m))
+        .min()
+        .map(|i| &demangled[..i])
+}
+
+/// Returns the crate name prefix of a demangled function name: everything before the first `::`,
+/// ignoring a leading `<` from qualified paths such as `<T as Trait>::method`. Names without a
+/// path yield `"NONE"`, matching the convention used when exporting invokes.
+#[must_use]
+pub fn crate_name(demangled: &str) -> &str {
+    demangled
+        .trim_start_matches('<')
+        .split_once("::")
+        .map_or("NONE", |(krate, _)| krate)
+}
+
+/// Returns the final path segment of a demangled function name, e.g. `parse` for
+/// `my_crate::parser::parse`.
+#[must_use]
+pub fn function_name(demangled: &str) -> &str {
+    demangled.rsplit("::").next().unwrap_or(demangled)
+}
+
+/// Returns the path of the module containing a demangled function, e.g. `my_crate::parser` for
+/// `my_crate::parser::parse`. Trait impl methods such as `<app::Foo as core::fmt::Debug>::fmt`
+/// are attributed to their self type, `app::Foo`, like inherent methods are. Names without a
+/// `::` are returned unchanged.
+#[must_use]
+pub fn module_path(demangled: &str) -> &str {
+    if let Some(self_type) = qualified_self_type(demangled) {
+        return self_type;
+    }
+    demangled
+        .rsplit_once("::")
+        .map_or(demangled, |(module, _)| module)
+}
+
+/// Returns the path of the self type of a qualified name, `<Type as Trait>::item` or
+/// `<Type>::item`, without references or generic arguments. `None` if `demangled` is not
+/// qualified.
+fn qualified_self_type(demangled: &str) -> Option<&str> {
+    let inner = demangled.strip_prefix('<')?;
+    let mut depth = 0usize;
+    let end = inner.char_indices().find_map(|(i, c)| match c {
+        '<' => {
+            depth += 1;
+            None
+        }
+        '>' if depth == 0 => Some(i),
+        '>' => {
+            depth -= 1;
+            None
+        }
+        ' ' if depth == 0 && inner[i..].starts_with(" as ") => Some(i),
+        _ => None,
+    })?;
+
+    let self_type = inner[..end]
+        .trim_start_matches('&')
+        .trim_start_matches("mut ")
+        .trim_start_matches("dyn ");
+    if self_type.starts_with('<') {
+        return qualified_self_type(self_type);
+    }
+    Some(self_type.split('<').next().unwrap_or(self_type))
+}
+
+/// Returns true if `demangled` has no Rust path, as is the case for foreign functions declared
+/// in an `extern "C"` block and for `#[no_mangle]` symbols, whose names are not mangled.
+#[must_use]
+pub fn is_extern_c(demangled: &str) -> bool {
+    !demangled.contains("::")
+}
+
+/// Computes Martin's instability metric `I = Ce / (Ca + Ce)` for each crate of `graph`, as
+/// determined by `crate_name`. `Ce` is the number of edges leaving the crate and `Ca` the number
+/// entering it. Scores near 1.0 mark crates which depend on many others, scores near 0.0 crates
+/// which many others depend on. Crates without any inter-crate edges are omitted.
+#[must_use]
+pub fn compute_crate_instability_metric(graph: &CallGraph) -> HashMap<String, f64> {
+    let mut coupling: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (caller, callee, _) in graph.edges() {
+        let (from, to) = (crate_name(caller), crate_name(callee));
+        if from != to {
+            coupling.entry(from).or_default().1 += 1;
+            coupling.entry(to).or_default().0 += 1;
+        }
+    }
+
+    coupling
+        .into_iter()
+        .map(|(krate, (afferent, efferent))| (krate.to_owned(), instability(afferent, efferent)))
+        .collect()
+}
+
+/// Martin's instability `Ce / (Ca + Ce)` of a module or crate with `ca` incoming and `ce`
+/// outgoing dependencies, from 0.0 (stable) to 1.0 (unstable). Zero when both are zero.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn instability(ca: usize, ce: usize) -> f64 {
+    if ca + ce == 0 {
+        0.0
+    } else {
+        ce as f64 / (ca + ce) as f64
+    }
+}
+
+/// Estimates the test coverage of the production `graph` from the call graph of its test suite,
+/// `test_graph`, built with `--test`, as the fraction of production functions called by some
+/// function of the test suite. A proxy which needs no instrumentation, see
+/// `CallGraph::coverage_report`. Zero for an empty `graph`.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn compute_test_coverage_estimate(graph: &CallGraph, test_graph: &CallGraph) -> f64 {
+    if graph.node_count() == 0 {
+        return 0.0;
+    }
+    graph.coverage_report(test_graph).covered.len() as f64 / graph.node_count() as f64
+}
+
+/// Returns the functions of `graph` from which no panic function, see `is_panic_function`, is
+/// reachable. Only meaningful for graphs extracted with `AnalysisConfig::include_panic_sites`,
+/// as the panic functions are otherwise dropped and every function appears panic-free.
+#[must_use]
+pub fn detect_panic_free_functions(graph: &CallGraph) -> HashSet<String> {
+    let mut may_panic = HashSet::new();
+    let mut stack: Vec<&str> = graph
+        .nodes()
+        .map(|n| n.demangled.as_str())
+        .filter(|n| is_panic_function(n))
+        .collect();
+    while let Some(current) = stack.pop() {
+        if may_panic.insert(current) {
+            stack.extend(graph.callers(current).filter(|c| !may_panic.contains(c)));
+        }
+    }
+
+    graph
+        .nodes()
+        .map(|n| n.demangled.as_str())
+        .filter(|n| !may_panic.contains(n))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// A single function within a `CallGraph`, identified by its demangled name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct FunctionNode {
+    /// The demangled function name, as produced by `analysis::extract_calls`.
+    pub demangled: String,
+    /// Set by `CallGraph::mark_public_api_surface` for functions exported by the crates public API.
+    pub is_public_api: bool,
+    /// Runtime hooks injected by a sanitizer build, see `compile::compile_with_sanitizer`.
+    pub is_sanitizer_hook: bool,
+    /// Nodes added manually through `CallGraph::add_synthetic_edge` rather than extracted.
+    pub is_synthetic: bool,
+    /// Functions containing a call through a function pointer or vtable, see
+    /// `analysis::detect_indirect_calls`.
+    pub has_indirect_calls: bool,
+    /// Functions storing to a global variable, see `analysis::detect_global_state_mutations`.
+    pub mutates_globals: bool,
+    /// Compiler generated state machines of async functions, see
+    /// `patterns::detect_async_function_calls`.
+    pub is_async: bool,
+    /// Times the function was executed according to profiling data, see
+    /// `CallGraph::label_hot_paths`.
+    pub execution_count: Option<u64>,
+    /// Profiling runtime functions added by `compile::compile_crate_with_coverage_instrumentation`.
+    pub is_coverage_counter: bool,
+    /// Where the function is defined, from the debug info of its bytecode.
+    pub location: Option<SourceLocation>,
+    /// Names of the clippy lints reported within the function, see
+    /// `lints::annotate_with_clippy_lints`.
+    pub clippy_warnings: Vec<String>,
+    /// Functions listed in `@llvm.global_ctors`, run before `main`, see
+    /// `analysis::detect_initialization_functions`.
+    pub is_global_ctor: bool,
+    /// Functions which cannot reach a panic function, see `detect_panic_free_functions`.
+    pub is_panic_free: bool,
+    /// LLVM types of the parameters, see `analysis::extract_function_argument_types`.
+    pub parameter_types: Vec<String>,
+    /// Number of calls to heap allocation functions, see `analysis::detect_allocation_sites`.
+    pub allocation_site_count: u32,
+    /// Functions referenced from a vtable or other table of function pointers, and so possible
+    /// targets of indirect calls, see `analysis::detect_dispatch_tables`.
+    pub in_vtable: bool,
+    /// Nodes recovered from symbol tables alone rather than from code, whose calls are a best
+    /// guess, see `analysis::extract_calls_from_pdb_file`.
+    pub is_approximate: bool,
+    /// Functions declared `unsafe fn`, see `CallGraph::mark_unsafe_functions`.
+    pub is_unsafe: bool,
+    /// The symbol name of the function, for nodes extracted from bytecode.
+    pub mangled: Option<String>,
+}
+
+/// A position within a source file, as recorded in debug info.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct SourceLocation {
+    /// Path of the file, as passed to rustc.
+    pub file: String,
+    pub line: u32,
+}
+impl FunctionNode {
+    #[must_use]
+    pub fn new<S: Into<String>>(demangled: S) -> Self {
+        let demangled = demangled.into();
+        Self {
+            is_sanitizer_hook: SANITIZER_PREFIXES.iter().any(|p| demangled.starts_with(p)),
+            is_async: async_function_name(&demangled).is_some(),
+            is_coverage_counter: demangled.starts_with(COVERAGE_PREFIX),
+            demangled,
+            ..Self::default()
+        }
+    }
+}
+
+/// Headline metrics of a `CallGraph`, see `CallGraph::summary`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CallSummary {
+    pub node_count: usize,
+    pub edge_count: usize,
+    /// Number of strongly connected components containing a cycle.
+    pub cycle_count: usize,
+    /// See `CallGraph::compute_modularity_score`.
+    pub modularity: f64,
+    /// See `CallGraph::compute_graph_entropy`.
+    pub out_degree_entropy: f64,
+    /// See `CallGraph::compute_in_degree_entropy`.
+    pub in_degree_entropy: f64,
+    /// See `CallGraph::compute_api_stability_score`. Only set by `CallGraph::summary_against`.
+    pub api_stability: Option<f64>,
+}
+
+/// Tree of the calls made from, or leading to, a function, see `CallGraph::build_call_tree` and
+/// `CallGraph::build_reverse_call_tree`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CallTree {
+    /// Demangled name of the function.
+    pub name: String,
+    /// Callees, or callers for a reverse tree, sorted by name.
+    pub children: Vec<CallTree>,
+}
+impl CallTree {
+    /// Maximum number of edges between this node and any of its leaves; a leaf has depth 0.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.children
+            .iter()
+            .map(|c| c.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Renders the tree one function per line, each indented two spaces deeper than its parent.
+    #[must_use]
+    pub fn to_indented_string(&self) -> String {
+        let mut out = String::new();
+        self.write_indented(&mut out, 0);
+        out
+    }
+
+    fn write_indented(&self, out: &mut String, level: usize) {
+        out.push_str(&"  ".repeat(level));
+        out.push_str(&self.name);
+        out.push('\n');
+        for child in &self.children {
+            child.write_indented(out, level + 1);
+        }
+    }
+}
+
+/// Edges of the call graphs of one crate built for Linux, macOS and Windows, split by the
+/// platforms they are present on, see `CallGraph::compare_platform_graphs`. Every list is sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PlatformDiff {
+    pub linux_only: Vec<(String, String)>,
+    pub macos_only: Vec<(String, String)>,
+    pub windows_only: Vec<(String, String)>,
+    pub linux_macos_only: Vec<(String, String)>,
+    pub linux_windows_only: Vec<(String, String)>,
+    pub macos_windows_only: Vec<(String, String)>,
+    pub all_platforms: Vec<(String, String)>,
+    /// Functions marked `FunctionNode::is_unsafe` in any of the graphs.
+    pub unsafe_functions: BTreeSet<String>,
+}
+impl PlatformDiff {
+    /// Edges only present on Linux or only on Windows with an `unsafe` caller or callee, which
+    /// are the most likely to hide platform specific memory safety bugs. Relies on the graphs
+    /// having been marked with `CallGraph::mark_unsafe_functions`.
+    #[must_use]
+    pub fn highest_risk_edges(&self) -> Vec<(String, String)> {
+        self.linux_only
+            .iter()
+            .chain(&self.windows_only)
+            .filter(|(caller, callee)| {
+                self.unsafe_functions.contains(caller) || self.unsafe_functions.contains(callee)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Production functions split by whether the test suite calls them, see
+/// `CallGraph::coverage_report`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CoverageReport {
+    /// Demangled names of the functions called from the test suite, sorted.
+    pub covered: Vec<String>,
+    /// Demangled names of the remaining functions, sorted.
+    pub uncovered: Vec<String>,
+}
+
+/// A single edge of the serialized `CallGraph` format.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SerializedEdge {
+    pub caller: String,
+    pub callee: String,
+    pub count: u32,
+    #[serde(default)]
+    pub synthetic: bool,
+}
+
+/// Serialization format of a `CallGraph`: its annotated nodes and counted edges.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SerializedGraph {
+    pub nodes: Vec<FunctionNode>,
+    pub edges: Vec<SerializedEdge>,
+}
+
+/// Directed call graph of demangled function names. Built from the `(caller, callee)` pairs
+/// returned by `analysis::extract_calls`; repeated pairs are collapsed into a single edge with a
+/// call count.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(into = "SerializedGraph", from = "SerializedGraph")]
+pub struct CallGraph {
+    nodes: BTreeMap<String, FunctionNode>,
+    edges: BTreeMap<(String, String), u32>,
+    callees: BTreeMap<String, BTreeSet<String>>,
+    callers: BTreeMap<String, BTreeSet<String>>,
+    synthetic_edges: BTreeSet<(String, String)>,
+    /// Number of edges whose caller and callee belong to different crates, kept up to date as
+    /// edges are added and removed.
+    inter_crate_edges: usize,
+}
+impl CallGraph {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a node for `name` if one does not already exist.
+    pub fn add_node<S: AsRef<str>>(&mut self, name: S) -> &mut FunctionNode {
+        let name = name.as_ref();
+        self.callees.entry(name.to_owned()).or_default();
+        self.callers.entry(name.to_owned()).or_default();
+        self.nodes
+            .entry(name.to_owned())
+            .or_insert_with(|| FunctionNode::new(name))
+    }
+
+    /// Records a call from `caller` to `callee`, creating either node if missing. Recording the
+    /// same pair again increments the edge call count.
+    pub fn add_edge<S1: AsRef<str>, S2: AsRef<str>>(&mut self, caller: S1, callee: S2) {
+        self.add_edge_with_count(caller, callee, 1);
+    }
+
+    /// Records `count` calls from `caller` to `callee`, creating either node if missing.
+    pub fn add_edge_with_count<S1: AsRef<str>, S2: AsRef<str>>(
+        &mut self,
+        caller: S1,
+        callee: S2,
+        count: u32,
+    ) {
+        let (caller, callee) = (caller.as_ref(), callee.as_ref());
+        self.add_node(caller);
+        self.add_node(callee);
+
+        match self.edges.entry((caller.to_owned(), callee.to_owned())) {
+            Entry::Vacant(entry) => {
+                entry.insert(count);
+                if crate_name(caller) != crate_name(callee) {
+                    self.inter_crate_edges += 1;
+                }
+            }
+            Entry::Occupied(mut entry) => {
+                let total = entry.get_mut();
+                *total = total.saturating_add(count);
+            }
+        }
+
+        self.callees
+            .entry(caller.to_owned())
+            .or_default()
+            .insert(callee.to_owned());
+        self.callers
+            .entry(callee.to_owned())
+            .or_default()
+            .insert(caller.to_owned());
+    }
+
+    /// Adds all nodes and edges of `other` to this graph. Call counts of edges present in both
+    /// are summed; nodes present in both keep the annotations of this graph.
+    pub fn merge(&mut self, other: CallGraph) {
+        for (name, node) in other.nodes {
+            if !self.contains(&name) {
+                *self.add_node(&name) = node;
+            }
+        }
+        for ((caller, callee), count) in other.edges {
+            let synthetic = other
+                .synthetic_edges
+                .contains(&(caller.clone(), callee.clone()));
+            if synthetic && self.call_count(&caller, &callee).is_none() {
+                self.synthetic_edges
+                    .insert((caller.clone(), callee.clone()));
+            }
+            self.add_edge_with_count(caller, callee, count);
+        }
+    }
+
+    /// Merges `graphs` into one as `merge` does, combining them pairwise in parallel on the rayon
+    /// thread pool. Meant for merging the per-crate graphs of a whole corpus.
+    #[must_use]
+    pub fn merge_parallel(graphs: Vec<CallGraph>) -> CallGraph {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        graphs
+            .into_par_iter()
+            .reduce(CallGraph::new, |mut merged, graph| {
+                merged.merge(graph);
+                merged
+            })
+    }
+
+    /// Removes `name` and all edges to or from it, returning the removed node.
+    pub fn remove_node(&mut self, name: &str) -> Option<FunctionNode> {
+        let node = self.nodes.remove(name)?;
+
+        let callees: Vec<_> = self.callees(name).map(str::to_owned).collect();
+        let callers: Vec<_> = self.callers(name).map(str::to_owned).collect();
+        for callee in callees {
+            self.remove_edge(name, &callee);
+        }
+        for caller in callers {
+            self.remove_edge(&caller, name);
+        }
+        self.callees.remove(name);
+        self.callers.remove(name);
+
+        Some(node)
+    }
+
+    /// Removes the `caller` -> `callee` edge, returning its call count. Both nodes are kept.
+    pub fn remove_edge(&mut self, caller: &str, callee: &str) -> Option<u32> {
+        let key = (caller.to_owned(), callee.to_owned());
+        let count = self.edges.remove(&key)?;
+
+        if crate_name(caller) != crate_name(callee) {
+            self.inter_crate_edges -= 1;
+        }
+        self.synthetic_edges.remove(&key);
+        if let Some(callees) = self.callees.get_mut(caller) {
+            callees.remove(callee);
+        }
+        if let Some(callers) = self.callers.get_mut(callee) {
+            callers.remove(caller);
+        }
+
+        Some(count)
+    }
+
+    /// Adds a call edge known through manual analysis but not visible statically, such as a call
+    /// through a function pointer. Missing nodes are created and flagged
+    /// `FunctionNode::is_synthetic`, and the edge is recorded as synthetic.
+    ///
+    /// # Errors
+    /// Returns `Error::EdgeExists` if the edge is already present in the graph.
+    pub fn add_synthetic_edge(&mut self, caller: &str, callee: &str) -> Result<(), Error> {
+        if self.call_count(caller, callee).is_some() {
+            return Err(Error::EdgeExists(caller.to_owned(), callee.to_owned()));
+        }
+
+        for name in [caller, callee] {
+            if !self.contains(name) {
+                self.add_node(name).is_synthetic = true;
+            }
+        }
+        self.add_edge(caller, callee);
+        self.synthetic_edges
+            .insert((caller.to_owned(), callee.to_owned()));
+
+        Ok(())
+    }
+
+    /// Reverts `add_synthetic_edge`, removing all synthetic edges and nodes.
+    pub fn remove_synthetic_nodes(&mut self) {
+        for (caller, callee) in std::mem::take(&mut self.synthetic_edges) {
+            self.remove_edge(&caller, &callee);
+        }
+
+        let synthetic: Vec<_> = self
+            .nodes()
+            .filter(|n| n.is_synthetic)
+            .map(|n| n.demangled.clone())
+            .collect();
+        for name in synthetic {
+            self.remove_node(&name);
+        }
+    }
+
+    /// Returns true if the `caller` -> `callee` edge was added by `add_synthetic_edge`.
+    #[must_use]
+    pub fn is_synthetic_edge(&self, caller: &str, callee: &str) -> bool {
+        self.synthetic_edges
+            .contains(&(caller.to_owned(), callee.to_owned()))
+    }
+
+    #[must_use]
+    pub fn node(&self, name: &str) -> Option<&FunctionNode> {
+        self.nodes.get(name)
+    }
+
+    pub fn node_mut(&mut self, name: &str) -> Option<&mut FunctionNode> {
+        self.nodes.get_mut(name)
+    }
+
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.nodes.contains_key(name)
+    }
+
+    /// All nodes, ordered by demangled name.
+    pub fn nodes(&self) -> impl Iterator<Item = &FunctionNode> {
+        self.nodes.values()
+    }
+
+    /// All edges as `(caller, callee, call_count)`, ordered by caller then callee.
+    pub fn edges(&self) -> impl Iterator<Item = (&str, &str, u32)> {
+        self.edges
+            .iter()
+            .map(|((caller, callee), count)| (caller.as_str(), callee.as_str(), *count))
+    }
+
+    /// All nodes for in-place annotation, ordered by demangled name.
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = &mut FunctionNode> {
+        self.nodes.values_mut()
+    }
+
+    /// All observed calls, repeating each edge once per recorded call, as the raw output of
+    /// `extract_calls` would without `AnalysisConfig::dedup_edges`.
+    pub fn raw_edges(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.edges().flat_map(|(caller, callee, count)| {
+            std::iter::repeat_n((caller, callee), count as usize)
+        })
+    }
+
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[must_use]
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Number of times the `caller` -> `callee` call was observed, if the edge exists.
+    #[must_use]
+    pub fn call_count(&self, caller: &str, callee: &str) -> Option<u32> {
+        self.edges
+            .get(&(caller.to_owned(), callee.to_owned()))
+            .copied()
+    }
+
+    /// Functions called directly by `name`.
+    pub fn callees(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.callees
+            .get(name)
+            .into_iter()
+            .flat_map(|s| s.iter().map(String::as_str))
+    }
+
+    /// Functions directly calling `name`.
+    pub fn callers(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.callers
+            .get(name)
+            .into_iter()
+            .flat_map(|s| s.iter().map(String::as_str))
+    }
+
+    #[must_use]
+    pub fn out_degree(&self, name: &str) -> usize {
+        self.callees.get(name).map_or(0, BTreeSet::len)
+    }
+
+    #[must_use]
+    pub fn in_degree(&self, name: &str) -> usize {
+        self.callers.get(name).map_or(0, BTreeSet::len)
+    }
+
+    /// Computes the local clustering coefficient of every node: the ratio of directed edges
+    /// present among a node's neighbors (callers and callees, ignoring self-calls) to the
+    /// `k * (k - 1)` edges possible between `k` neighbors. Nodes with fewer than two neighbors
+    /// have a coefficient of `0.0`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn compute_clustering_coefficient(&self) -> HashMap<String, f64> {
+        self.nodes
+            .keys()
+            .map(|name| {
+                let neighbors: HashSet<&str> = self
+                    .callees(name)
+                    .chain(self.callers(name))
+                    .filter(|n| *n != name)
+                    .collect();
+
+                let k = neighbors.len();
+                if k < 2 {
+                    return (name.clone(), 0.0);
+                }
+
+                let links = neighbors
+                    .iter()
+                    .map(|n| {
+                        self.callees(n)
+                            .filter(|c| *c != *n && neighbors.contains(c))
+                            .count()
+                    })
+                    .sum::<usize>();
+
+                (name.clone(), links as f64 / (k * (k - 1)) as f64)
+            })
+            .collect()
+    }
+
+    /// Average of `compute_clustering_coefficient` across all nodes. Returns `0.0` for an empty
+    /// graph.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn global_clustering_coefficient(&self) -> f64 {
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+
+        self.compute_clustering_coefficient().values().sum::<f64>() / self.nodes.len() as f64
+    }
+
+    /// Computes the Newman-Girvan modularity `Q` of the graph, partitioned into modules by
+    /// `crate_name`. As the graph is directed, the expected fraction of edges within module `i` is
+    /// `a_out_i * a_in_i`, where `a_out_i` and `a_in_i` are the fractions of edges leaving and
+    /// entering it, giving `Q = sum(e_ii - a_out_i * a_in_i)`. Values close to `1.0` indicate well
+    /// isolated crates. Returns `0.0` for a graph without edges.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn compute_modularity_score(&self) -> f64 {
+        let m = self.edges.len();
+        if m == 0 {
+            return 0.0;
+        }
+
+        // module -> (edges within, edges leaving any member, edges entering any member)
+        let mut modules: HashMap<&str, (usize, usize, usize)> = HashMap::new();
+        for (caller, callee) in self.edges.keys() {
+            let (src, dst) = (crate_name(caller), crate_name(callee));
+            modules.entry(src).or_default().1 += 1;
+            modules.entry(dst).or_default().2 += 1;
+            if src == dst {
+                modules.entry(src).or_default().0 += 1;
+            }
+        }
+
+        let m = m as f64;
+        modules
+            .values()
+            .map(|(within, out, into)| *within as f64 / m - (*out as f64 / m) * (*into as f64 / m))
+            .sum()
+    }
+
+    /// All nodes reachable from `name` by following calls, including `name` itself. Empty if
+    /// `name` is not in the graph.
+    #[must_use]
+    pub fn reachable_from(&self, name: &str) -> HashSet<&str> {
+        let mut visited = HashSet::new();
+        let Some((start, _)) = self.nodes.get_key_value(name) else {
+            return visited;
+        };
+
+        let mut stack = vec![start.as_str()];
+        while let Some(current) = stack.pop() {
+            if visited.insert(current) {
+                stack.extend(self.callees(current).filter(|c| !visited.contains(c)));
+            }
+        }
+
+        visited
+    }
+
+    /// All nodes with at least one clippy warning attributed to them.
+    #[must_use]
+    pub fn functions_with_clippy_warnings(&self) -> Vec<&FunctionNode> {
+        self.nodes()
+            .filter(|n| !n.clippy_warnings.is_empty())
+            .collect()
+    }
+
+    /// Nodes with no callers, the natural entry points of the graph.
+    pub fn roots(&self) -> impl Iterator<Item = &str> {
+        self.callers
+            .iter()
+            .filter(|(_, callers)| callers.is_empty())
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Partitions the graph into layers by minimum call depth from the entry points: layer 0 holds
+    /// the nodes without callers, layer 1 the functions they call directly, and so on. Nodes only
+    /// reachable through cycles, which no entry point reaches, are placed in layer `usize::MAX`.
+    /// Names within each layer are sorted.
+    #[must_use]
+    pub fn compute_abstract_interpretation_layers(&self) -> HashMap<usize, Vec<String>> {
+        let mut depth: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut queue: std::collections::VecDeque<&str> = self.roots().collect();
+        for r in &queue {
+            depth.insert(r, 0);
+        }
+
+        while let Some(current) = queue.pop_front() {
+            let next = depth[current] + 1;
+            for callee in self.callees(current) {
+                if !depth.contains_key(callee) {
+                    depth.insert(callee, next);
+                    queue.push_back(callee);
+                }
+            }
+        }
+
+        let mut layers: HashMap<usize, Vec<String>> = HashMap::new();
+        for name in self.nodes.keys() {
+            let layer = depth.get(name.as_str()).copied().unwrap_or(usize::MAX);
+            layers.entry(layer).or_default().push(name.clone());
+        }
+
+        layers
+    }
+
+    /// Computes the strongly connected components of the graph with Tarjan's algorithm. Components
+    /// are returned in reverse topological order of the condensation (callees before callers),
+    /// each with its names sorted. Every node belongs to exactly one component.
+    #[must_use]
+    pub fn strongly_connected_components(&self) -> Vec<Vec<String>> {
+        let names: Vec<&str> = self.nodes.keys().map(String::as_str).collect();
+        let ids: HashMap<&str, usize> = names.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        let adjacency: Vec<Vec<usize>> = names
+            .iter()
+            .map(|n| self.callees(n).map(|c| ids[c]).collect())
+            .collect();
+
+        let mut index = vec![usize::MAX; names.len()];
+        let mut low = vec![0; names.len()];
+        let mut on_stack = vec![false; names.len()];
+        let mut stack = Vec::new();
+        let mut next_index = 0;
+        let mut components = Vec::new();
+
+        for start in 0..names.len() {
+            if index[start] != usize::MAX {
+                continue;
+            }
+
+            // Iterative DFS; each frame is (node, position of the next callee to visit).
+            index[start] = next_index;
+            low[start] = next_index;
+            next_index += 1;
+            stack.push(start);
+            on_stack[start] = true;
+            let mut frames = vec![(start, 0)];
+
+            while let Some(frame) = frames.last_mut() {
+                let (v, child) = *frame;
+                if let Some(&w) = adjacency[v].get(child) {
+                    frame.1 += 1;
+                    if index[w] == usize::MAX {
+                        index[w] = next_index;
+                        low[w] = next_index;
+                        next_index += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        frames.push((w, 0));
+                    } else if on_stack[w] {
+                        low[v] = low[v].min(index[w]);
+                    }
+                    continue;
+                }
+
+                frames.pop();
+                if let Some(&(parent, _)) = frames.last() {
+                    low[parent] = low[parent].min(low[v]);
+                }
+
+                if low[v] == index[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(names[w].to_owned());
+                        if w == v {
+                            break;
+                        }
+                    }
+                    component.sort();
+                    components.push(component);
+                }
+            }
+        }
+
+        components
+    }
+
+    /// Builds the tree of calls made from `root`, following callees up to `max_depth` edges deep.
+    /// A function already on the path from the root is not expanded again, so recursion ends at
+    /// the recursive call.
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownFunction` if `root` is not in the graph.
+    pub fn build_call_tree(&self, root: &str, max_depth: usize) -> Result<CallTree, Error> {
+        self.call_tree(root, max_depth, false)
+    }
+
+    /// Builds the tree of functions which eventually call `sink`, following callers up to
+    /// `max_depth` edges deep, as `build_call_tree` does for callees.
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownFunction` if `sink` is not in the graph.
+    pub fn build_reverse_call_tree(&self, sink: &str, max_depth: usize) -> Result<CallTree, Error> {
+        self.call_tree(sink, max_depth, true)
+    }
+
+    fn call_tree(&self, name: &str, max_depth: usize, reverse: bool) -> Result<CallTree, Error> {
+        fn expand<'a>(
+            graph: &'a CallGraph,
+            name: &'a str,
+            depth_left: usize,
+            reverse: bool,
+            path: &mut Vec<&'a str>,
+        ) -> CallTree {
+            let mut children = Vec::new();
+            if depth_left > 0 {
+                path.push(name);
+                let next: Vec<&str> = if reverse {
+                    graph.callers(name).filter(|c| !path.contains(c)).collect()
+                } else {
+                    graph.callees(name).filter(|c| !path.contains(c)).collect()
+                };
+                for child in next {
+                    children.push(expand(graph, child, depth_left - 1, reverse, path));
+                }
+                path.pop();
+            }
+
+            CallTree {
+                name: name.to_owned(),
+                children,
+            }
+        }
+
+        let Some((name, _)) = self.nodes.get_key_value(name) else {
+            return Err(Error::UnknownFunction(name.to_owned()));
+        };
+        Ok(expand(self, name, max_depth, reverse, &mut Vec::new()))
+    }
+
+    /// Finds the bridge edges of the graph: calls whose removal disconnects a pair of functions
+    /// that were connected before. Edges are treated as undirected, so a pair of functions calling
+    /// each other is never a bridge, and bridges are found with Tarjan's low-link algorithm.
+    /// Returns the `(caller, callee)` pairs sorted by name.
+    #[must_use]
+    pub fn find_bridge_edges(&self) -> Vec<(String, String)> {
+        let names: Vec<&str> = self.nodes.keys().map(String::as_str).collect();
+        let ids: HashMap<&str, usize> = names.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        let edges: Vec<(usize, usize)> = self
+            .edges()
+            .filter(|(caller, callee, _)| caller != callee)
+            .map(|(caller, callee, _)| (ids[caller], ids[callee]))
+            .collect();
+        let mut adjacency = vec![Vec::new(); names.len()];
+        for (edge, &(caller, callee)) in edges.iter().enumerate() {
+            adjacency[caller].push((callee, edge));
+            adjacency[callee].push((caller, edge));
+        }
+
+        let mut index = vec![usize::MAX; names.len()];
+        let mut low = vec![0; names.len()];
+        let mut next_index = 0;
+        let mut bridges = Vec::new();
+
+        for start in 0..names.len() {
+            if index[start] != usize::MAX {
+                continue;
+            }
+
+            // Iterative DFS; each frame is (node, edge it was entered by, position of the next
+            // neighbour to visit).
+            index[start] = next_index;
+            low[start] = next_index;
+            next_index += 1;
+            let mut frames = vec![(start, usize::MAX, 0)];
+
+            while let Some((v, entered_by, child)) = frames.last().copied() {
+                if let Some(&(w, edge)) = adjacency[v].get(child) {
+                    if let Some(frame) = frames.last_mut() {
+                        frame.2 += 1;
+                    }
+                    if edge == entered_by {
+                        continue;
+                    }
+                    if index[w] == usize::MAX {
+                        index[w] = next_index;
+                        low[w] = next_index;
+                        next_index += 1;
+                        frames.push((w, edge, 0));
+                    } else {
+                        low[v] = low[v].min(index[w]);
+                    }
+                    continue;
+                }
+
+                frames.pop();
+                if let Some(&(parent, _, _)) = frames.last() {
+                    low[parent] = low[parent].min(low[v]);
+                    if low[v] > index[parent] {
+                        let (caller, callee) = edges[entered_by];
+                        bridges.push((names[caller].to_owned(), names[callee].to_owned()));
+                    }
+                }
+            }
+        }
+
+        bridges.sort();
+        bridges
+    }
+
+    /// Maps every node to the index of its strongly connected component. Components are numbered
+    /// from 0 in topological order of the condensation, callers before callees, so the index is
+    /// stable for a given graph.
+    #[must_use]
+    pub fn compute_strongly_connected_component_map(&self) -> HashMap<String, usize> {
+        self.strongly_connected_components()
+            .into_iter()
+            .rev()
+            .enumerate()
+            .flat_map(|(id, component)| component.into_iter().map(move |name| (name, id)))
+            .collect()
+    }
+
+    /// All nodes in the strongly connected component `scc_id`, as numbered by
+    /// `compute_strongly_connected_component_map`, sorted by name.
+    #[must_use]
+    pub fn nodes_in_scc(&self, scc_id: usize) -> Vec<&FunctionNode> {
+        let map = self.compute_strongly_connected_component_map();
+        self.nodes()
+            .filter(|n| map[&n.demangled] == scc_id)
+            .collect()
+    }
+
+    /// Returns the strongly connected components which contain a cycle: those with more than one
+    /// function, or a single directly recursive function.
+    #[must_use]
+    pub fn find_cycles(&self) -> Vec<Vec<String>> {
+        self.strongly_connected_components()
+            .into_iter()
+            .filter(|c| c.len() > 1 || self.call_count(&c[0], &c[0]).is_some())
+            .collect()
+    }
+
+    /// Enumerates the elementary circuits of the graph with at most `max_length` functions,
+    /// stopping once `max_cycles` have been found, using Johnson's algorithm. Each cycle is
+    /// rotated to start at its lexicographically smallest function, and is found exactly once by
+    /// only searching from that function through larger ones within its strongly connected
+    /// component. Functions from which the start cannot be reached are blocked until one of
+    /// their callees is unblocked; a search cut short by `max_length` leaves its functions
+    /// unblocked, as a shorter path may still close a cycle through them.
+    #[must_use]
+    pub fn find_all_cycles(&self, max_length: usize, max_cycles: usize) -> Vec<Vec<String>> {
+        let mut cycles = Vec::new();
+        if max_length == 0 {
+            return cycles;
+        }
+
+        for component in self.find_cycles() {
+            let members: HashSet<&str> = component.iter().map(String::as_str).collect();
+            for start in &component {
+                if cycles.len() >= max_cycles {
+                    return cycles;
+                }
+                let mut search = CircuitSearch {
+                    graph: self,
+                    component: &members,
+                    start,
+                    limits: (max_length, max_cycles),
+                    path: Vec::new(),
+                    blocked: HashSet::new(),
+                    blocked_by: HashMap::new(),
+                    cycles: &mut cycles,
+                };
+                search.circuit(start);
+            }
+        }
+
+        cycles
+    }
+
+    /// Computes the headline metrics of the graph.
+    #[must_use]
+    pub fn summary(&self) -> CallSummary {
+        CallSummary {
+            node_count: self.node_count(),
+            edge_count: self.edge_count(),
+            cycle_count: self.find_cycles().len(),
+            modularity: self.compute_modularity_score(),
+            out_degree_entropy: self.compute_graph_entropy(),
+            in_degree_entropy: self.compute_in_degree_entropy(),
+            api_stability: None,
+        }
+    }
+
+    /// Compares the call graphs of one crate built for each platform, splitting their edges into
+    /// all seven combinations of the platforms they are present on.
+    #[must_use]
+    pub fn compare_platform_graphs(
+        linux: &CallGraph,
+        macos: &CallGraph,
+        windows: &CallGraph,
+    ) -> PlatformDiff {
+        let edge_set = |graph: &CallGraph| -> BTreeSet<(String, String)> {
+            graph.edges.keys().cloned().collect()
+        };
+        let (linux_edges, macos_edges, windows_edges) =
+            (edge_set(linux), edge_set(macos), edge_set(windows));
+
+        let mut diff = PlatformDiff {
+            unsafe_functions: [linux, macos, windows]
+                .iter()
+                .flat_map(|g| g.nodes())
+                .filter(|n| n.is_unsafe)
+                .map(|n| n.demangled.clone())
+                .collect(),
+            ..PlatformDiff::default()
+        };
+        let all_edges: BTreeSet<&(String, String)> = linux_edges
+            .iter()
+            .chain(&macos_edges)
+            .chain(&windows_edges)
+            .collect();
+        for edge in all_edges {
+            let present = (
+                linux_edges.contains(edge),
+                macos_edges.contains(edge),
+                windows_edges.contains(edge),
+            );
+            let list = match present {
+                (true, false, false) => &mut diff.linux_only,
+                (false, true, false) => &mut diff.macos_only,
+                (false, false, true) => &mut diff.windows_only,
+                (true, true, false) => &mut diff.linux_macos_only,
+                (true, false, true) => &mut diff.linux_windows_only,
+                (false, true, true) => &mut diff.macos_windows_only,
+                (true, true, true) => &mut diff.all_platforms,
+                (false, false, false) => continue,
+            };
+            list.push(edge.clone());
+        }
+
+        diff
+    }
+
+    /// Splits the functions of this production graph by whether they are a callee in
+    /// `test_graph`, the call graph of the test suite, see `compute_test_coverage_estimate`.
+    #[must_use]
+    pub fn coverage_report(&self, test_graph: &CallGraph) -> CoverageReport {
+        let (covered, uncovered) = self
+            .nodes
+            .keys()
+            .cloned()
+            .partition(|name| test_graph.callers(name).next().is_some());
+
+        CoverageReport { covered, uncovered }
+    }
+
+    /// Returns `summary` with `CallSummary::api_stability` computed against `previous_graph`.
+    #[must_use]
+    pub fn summary_against(&self, previous_graph: &CallGraph) -> CallSummary {
+        CallSummary {
+            api_stability: Some(self.compute_api_stability_score(previous_graph)),
+            ..self.summary()
+        }
+    }
+
+    /// Scores how much of the API of `previous_graph` survived into this graph, from 0.0 (all
+    /// changed) to 1.0 (unchanged). The score is the average Jaccard similarity between the
+    /// callee sets of each entry point of `previous_graph` there and here; an entry point
+    /// missing here scores 0.0. A previous graph without entry points scores 1.0.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn compute_api_stability_score(&self, previous_graph: &CallGraph) -> f64 {
+        let scores: Vec<f64> = previous_graph
+            .roots()
+            .map(|root| {
+                if !self.contains(root) {
+                    return 0.0;
+                }
+                let before: BTreeSet<&str> = previous_graph.callees(root).collect();
+                let after: BTreeSet<&str> = self.callees(root).collect();
+                let union = before.union(&after).count();
+                if union == 0 {
+                    1.0
+                } else {
+                    before.intersection(&after).count() as f64 / union as f64
+                }
+            })
+            .collect();
+
+        if scores.is_empty() {
+            1.0
+        } else {
+            scores.iter().sum::<f64>() / scores.len() as f64
+        }
+    }
+
+    /// Shannon entropy, in bits, of the distribution of `degree` over all nodes.
+    #[allow(clippy::cast_precision_loss)]
+    fn degree_entropy(&self, degree: fn(&Self, &str) -> usize) -> f64 {
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for node in self.nodes() {
+            *counts.entry(degree(self, &node.demangled)).or_default() += 1;
+        }
+
+        let total = self.node_count() as f64;
+        -counts
+            .into_values()
+            .map(|count| {
+                let p = count as f64 / total;
+                p * p.log2()
+            })
+            .sum::<f64>()
+    }
+
+    /// Shannon entropy `H = -sum(p_k * log2(p_k))` of the out-degree distribution, where `p_k` is
+    /// the fraction of nodes with out-degree `k`. Zero when every node has the same out-degree,
+    /// and higher the more varied the degrees are.
+    #[must_use]
+    pub fn compute_graph_entropy(&self) -> f64 {
+        self.degree_entropy(Self::out_degree)
+    }
+
+    /// Shannon entropy of the in-degree distribution, as `compute_graph_entropy`.
+    #[must_use]
+    pub fn compute_in_degree_entropy(&self) -> f64 {
+        self.degree_entropy(Self::in_degree)
+    }
+
+    /// Returns a new graph holding only the edges accepted by `keep`, along with their endpoints.
+    /// Node annotations and call counts are preserved.
+    #[must_use]
+    pub fn filter_edges<F>(&self, mut keep: F) -> CallGraph
+    where
+        F: FnMut(&str, &str) -> bool,
+    {
+        let mut graph = CallGraph::new();
+
+        for (caller, callee, count) in self.edges().filter(|(a, b, _)| keep(a, b)) {
+            for name in [caller, callee] {
+                if !graph.contains(name) {
+                    *graph.add_node(name) = self.nodes[name].clone();
+                }
+            }
+            graph.add_edge_with_count(caller, callee, count);
+            if self.is_synthetic_edge(caller, callee) {
+                graph
+                    .synthetic_edges
+                    .insert((caller.to_owned(), callee.to_owned()));
+            }
+        }
+
+        graph
+    }
+
+    /// Computes the afferent (`Ca`, incoming) and efferent (`Ce`, outgoing) coupling of every
+    /// first level module, the `crate_name::module` prefix of each function's `module_path`.
+    /// Only edges between different modules are counted. Returns `(Ca, Ce)` keyed by module; see
+    /// `instability` for combining them.
+    #[must_use]
+    pub fn compute_afferent_efferent_coupling(&self) -> HashMap<String, (usize, usize)> {
+        fn first_level_module(demangled: &str) -> &str {
+            let path = module_path(demangled);
+            match path.match_indices("::").nth(1) {
+                Some((end, _)) => &path[..end],
+                None => path,
+            }
+        }
+
+        let mut coupling: HashMap<String, (usize, usize)> = HashMap::new();
+        for (caller, callee, _) in self.edges() {
+            let (from, to) = (first_level_module(caller), first_level_module(callee));
+            if from != to {
+                coupling.entry(from.to_owned()).or_default().1 += 1;
+                coupling.entry(to.to_owned()).or_default().0 += 1;
+            }
+        }
+
+        coupling
+    }
+
+    /// Returns the graph of calls between different crates only, as determined by `crate_name`.
+    /// Functions only involved in intra-crate calls are dropped.
+    #[must_use]
+    pub fn retain_only_cross_crate_edges(&self) -> CallGraph {
+        self.filter_edges(|caller, callee| crate_name(caller) != crate_name(callee))
+    }
+
+    /// Number of edges between different crates.
+    #[must_use]
+    pub fn inter_crate_edge_count(&self) -> usize {
+        self.inter_crate_edges
+    }
+
+    /// All nodes flagged `FunctionNode::mutates_globals`.
+    #[must_use]
+    pub fn global_state_mutators(&self) -> Vec<&FunctionNode> {
+        self.nodes().filter(|n| n.mutates_globals).collect()
+    }
+
+    /// Sets `FunctionNode::is_panic_free` on every node, see `detect_panic_free_functions`.
+    pub fn mark_panic_free_functions(&mut self) {
+        let panic_free = detect_panic_free_functions(self);
+        for node in self.nodes_mut() {
+            node.is_panic_free = panic_free.contains(&node.demangled);
+        }
+    }
+
+    /// All nodes making at least one heap allocation, with their `allocation_site_count`, most
+    /// allocations first.
+    #[must_use]
+    pub fn functions_with_allocations(&self) -> Vec<(&FunctionNode, u32)> {
+        let mut functions: Vec<_> = self
+            .nodes()
+            .filter(|n| n.allocation_site_count > 0)
+            .map(|n| (n, n.allocation_site_count))
+            .collect();
+        functions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.demangled.cmp(&b.0.demangled)));
+
+        functions
+    }
+
+    /// All nodes flagged `FunctionNode::is_global_ctor`.
+    #[must_use]
+    pub fn detect_initialization_functions(&self) -> Vec<&FunctionNode> {
+        self.nodes().filter(|n| n.is_global_ctor).collect()
+    }
+
+    /// Returns the subgraph of everything reachable from the global constructors, i.e. all code
+    /// which may run before `main`.
+    #[must_use]
+    pub fn initialization_order_graph(&self) -> CallGraph {
+        let keep: HashSet<&str> = self
+            .detect_initialization_functions()
+            .into_iter()
+            .flat_map(|n| self.reachable_from(&n.demangled))
+            .collect();
+
+        let mut graph = self.filter_edges(|a, b| keep.contains(a) && keep.contains(b));
+        for name in keep {
+            if !graph.contains(name) {
+                *graph.add_node(name) = self.nodes[name].clone();
+            }
+        }
+
+        graph
+    }
+
+    /// Removes every node, and its edges, for which `keep` returns false.
+    pub fn retain_nodes<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&FunctionNode) -> bool,
+    {
+        let removed: Vec<_> = self
+            .nodes()
+            .filter(|n| !keep(n))
+            .map(|n| n.demangled.clone())
+            .collect();
+
+        for name in removed {
+            self.remove_node(&name);
+        }
+    }
+
+    /// Immediate dominators of every node reachable from `entry`, with `entry` mapped to itself,
+    /// computed with the iterative algorithm of Cooper, Harvey and Kennedy. Empty if `entry` is
+    /// not in the graph.
+    fn immediate_dominators(&self, entry: &str) -> HashMap<&str, &str> {
+        let mut idom = HashMap::new();
+        let Some((entry, _)) = self.nodes.get_key_value(entry) else {
+            return idom;
+        };
+
+        // Reverse postorder of an iterative DFS; each frame is (node, its callees, position of
+        // the next callee to visit).
+        let mut visited = HashSet::from([entry.as_str()]);
+        let mut postorder = Vec::new();
+        let mut frames = vec![(entry.as_str(), self.callees(entry).collect::<Vec<_>>(), 0)];
+        while let Some((node, callees, next)) = frames.last_mut() {
+            if let Some(&callee) = callees.get(*next) {
+                *next += 1;
+                if visited.insert(callee) {
+                    frames.push((callee, self.callees(callee).collect(), 0));
+                }
+            } else {
+                postorder.push(*node);
+                frames.pop();
+            }
+        }
+        let order: HashMap<&str, usize> =
+            postorder.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+        idom.insert(entry.as_str(), entry.as_str());
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in postorder.iter().rev().skip(1) {
+                let mut new_idom: Option<&str> = None;
+                for pred in self.callers(node).filter(|p| idom.contains_key(p)) {
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(mut other) => {
+                            let mut pred = pred;
+                            while pred != other {
+                                while order[pred] < order[other] {
+                                    pred = idom[pred];
+                                }
+                                while order[other] < order[pred] {
+                                    other = idom[other];
+                                }
+                            }
+                            pred
+                        }
+                    });
+                }
+
+                if let Some(new_idom) = new_idom {
+                    if idom.insert(node, new_idom) != Some(new_idom) {
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        idom
+    }
+
+    /// Computes the dominance frontier of every node reachable from `entry`: the functions where
+    /// paths from it meet paths which bypass it, found from `immediate_dominators` with the
+    /// algorithm of Cooper, Harvey and Kennedy. Empty if `entry` is not in the graph.
+    #[must_use]
+    pub fn compute_function_dominance_frontier(
+        &self,
+        entry: &str,
+    ) -> HashMap<String, HashSet<String>> {
+        let idom = self.immediate_dominators(entry);
+        let mut frontier: HashMap<String, HashSet<String>> = idom
+            .keys()
+            .map(|n| ((*n).to_owned(), HashSet::new()))
+            .collect();
+
+        for (&node, &node_idom) in &idom {
+            let preds: Vec<&str> = self
+                .callers(node)
+                .filter(|p| idom.contains_key(p))
+                .collect();
+            if preds.len() < 2 {
+                continue;
+            }
+
+            for mut runner in preds {
+                while runner != node_idom {
+                    frontier
+                        .entry(runner.to_owned())
+                        .or_default()
+                        .insert(node.to_owned());
+                    runner = idom[runner];
+                }
+            }
+        }
+
+        frontier
+    }
+
+    /// Returns the functions lying on every path from every one of `entry_points` to every sink
+    /// (function without callees) it reaches, found as the common dominators of those sinks. The
+    /// entry points and sinks themselves are not included. Entry points missing from the graph
+    /// are ignored.
+    #[must_use]
+    pub fn compute_critical_functions(&self, entry_points: &[&str]) -> HashSet<String> {
+        let mut critical: Option<HashSet<&str>> = None;
+
+        for entry in entry_points.iter().filter(|e| self.contains(e)) {
+            let idom = self.immediate_dominators(entry);
+            let sinks: Vec<&str> = idom
+                .keys()
+                .copied()
+                .filter(|n| self.out_degree(n) == 0)
+                .collect();
+
+            for sink in sinks {
+                let mut dominators = HashSet::new();
+                let mut current = idom[sink];
+                while current != idom[current] {
+                    dominators.insert(current);
+                    current = idom[current];
+                }
+
+                critical = Some(match critical {
+                    None => dominators,
+                    Some(found) => found.intersection(&dominators).copied().collect(),
+                });
+            }
+        }
+
+        critical
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|n| !entry_points.contains(n))
+            .map(ToOwned::to_owned)
+            .collect()
+    }
+
+    /// Eccentricity of every node: the largest call distance from it to any node it reaches.
+    /// Nodes which reach no other node get `usize::MAX`.
+    #[must_use]
+    pub fn compute_node_eccentricity(&self) -> HashMap<String, usize> {
+        self.nodes()
+            .map(|n| {
+                let distances = self.distances_from(&n.demangled);
+                let eccentricity = distances.into_values().max().filter(|d| *d > 0);
+                (n.demangled.clone(), eccentricity.unwrap_or(usize::MAX))
+            })
+            .collect()
+    }
+
+    /// Smallest eccentricity of any node, ignoring nodes which reach no other node. Zero if no
+    /// node reaches another.
+    #[must_use]
+    pub fn radius(&self) -> usize {
+        self.compute_node_eccentricity()
+            .into_values()
+            .filter(|e| *e != usize::MAX)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Largest eccentricity of any node, ignoring nodes which reach no other node. Zero if no
+    /// node reaches another.
+    #[must_use]
+    pub fn diameter(&self) -> usize {
+        self.compute_node_eccentricity()
+            .into_values()
+            .filter(|e| *e != usize::MAX)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Breadth-first call distances from `entry` to every node it reaches, including `entry` at
+    /// distance 0. Empty if `entry` is not in the graph.
+    #[must_use]
+    pub fn distances_from(&self, entry: &str) -> HashMap<&str, usize> {
+        let mut distances = HashMap::new();
+        let Some((start, _)) = self.nodes.get_key_value(entry) else {
+            return distances;
+        };
+
+        distances.insert(start.as_str(), 0);
+        let mut queue = std::collections::VecDeque::from([start.as_str()]);
+        while let Some(current) = queue.pop_front() {
+            let next = distances[current] + 1;
+            for callee in self.callees(current) {
+                if !distances.contains_key(callee) {
+                    distances.insert(callee, next);
+                    queue.push_back(callee);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Removes all nodes matching the default `AnalysisConfig::test_function_patterns`, returning
+    /// how many were removed.
+    pub fn remove_test_functions(&mut self) -> usize {
+        self.remove_test_functions_matching(&AnalysisConfig::default().test_function_patterns)
+    }
+
+    /// Removes all nodes, and their edges, whose demangled name matches any of `patterns`,
+    /// returning how many were removed.
+    pub fn remove_test_functions_matching(&mut self, patterns: &[Regex]) -> usize {
+        let before = self.node_count();
+        self.retain_nodes(|n| !patterns.iter().any(|p| p.is_match(&n.demangled)));
+        before - self.node_count()
+    }
+
+    /// Computes HITS `(hub_scores, authority_scores)` over `iterations` rounds. A node's
+    /// authority is the sum of the hub scores of its callers, and its hub score the sum of the
+    /// authorities of its callees; both are L2 normalized after every round.
+    #[must_use]
+    pub fn compute_hits(&self, iterations: u32) -> (HashMap<String, f64>, HashMap<String, f64>) {
+        fn normalize(scores: &mut HashMap<String, f64>) {
+            let norm = scores.values().map(|s| s * s).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                for s in scores.values_mut() {
+                    *s /= norm;
+                }
+            }
+        }
+
+        let mut hubs: HashMap<String, f64> = self.nodes.keys().map(|n| (n.clone(), 1.0)).collect();
+        let mut authorities = hubs.clone();
+
+        for _ in 0..iterations {
+            for (name, authority) in &mut authorities {
+                *authority = self.callers(name).map(|c| hubs[c]).sum();
+            }
+            normalize(&mut authorities);
+
+            for (name, hub) in &mut hubs {
+                *hub = self.callees(name).map(|c| authorities[c]).sum();
+            }
+            normalize(&mut hubs);
+        }
+
+        (hubs, authorities)
+    }
+
+    /// Nodes sorted by descending HITS hub score, i.e. functions calling many widely called
+    /// functions.
+    #[must_use]
+    pub fn compute_hub_nodes(&self) -> Vec<(&FunctionNode, f64)> {
+        self.ranked_by(self.compute_hits(HITS_ITERATIONS).0)
+    }
+
+    /// Nodes sorted by descending HITS authority score, i.e. the most called functions and
+    /// natural candidates for API surface hardening.
+    #[must_use]
+    pub fn compute_authority_nodes(&self) -> Vec<(&FunctionNode, f64)> {
+        self.ranked_by(self.compute_hits(HITS_ITERATIONS).1)
+    }
+
+    fn ranked_by(&self, scores: HashMap<String, f64>) -> Vec<(&FunctionNode, f64)> {
+        let mut ranked: Vec<_> = scores
+            .into_iter()
+            .filter_map(|(name, score)| Some((self.nodes.get(&name)?, score)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.demangled.cmp(&b.0.demangled)));
+        ranked
+    }
+
+    /// Sets `FunctionNode::execution_count` from a profile of `function_name:count` lines, as
+    /// exported from `perf`, `gprof` or `cargo-flamegraph`. The count is split off at the last
+    /// `:`, so demangled paths are accepted as is. Blank lines are skipped and functions not in
+    /// the graph are ignored.
+    ///
+    /// # Errors
+    /// Returns `Error::IoError` if the file cannot be read, or `Error::ProfileDataError` for a
+    /// line without a valid count.
+    pub fn label_hot_paths(&mut self, profile_data_path: &Path) -> Result<(), Error> {
+        let data = std::fs::read_to_string(profile_data_path)?;
+
+        for (i, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, count) = line
+                .rsplit_once(':')
+                .and_then(|(name, count)| Some((name.trim(), count.trim().parse::<u64>().ok()?)))
+                .ok_or_else(|| Error::ProfileDataError(i + 1, line.to_owned()))?;
+
+            if let Some(node) = self.nodes.get_mut(name) {
+                node.execution_count = Some(count);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the subgraph induced by the `top_n` nodes with the highest
+    /// `FunctionNode::execution_count` and their immediate callers and callees.
+    #[must_use]
+    pub fn hot_path_subgraph(&self, top_n: usize) -> CallGraph {
+        let mut hottest: Vec<_> = self
+            .nodes()
+            .filter_map(|n| Some((n.demangled.as_str(), n.execution_count?)))
+            .collect();
+        hottest.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        hottest.truncate(top_n);
+
+        let mut keep = HashSet::new();
+        for (name, _) in hottest {
+            keep.insert(name);
+            keep.extend(self.callers(name));
+            keep.extend(self.callees(name));
+        }
+
+        let mut graph = self.filter_edges(|a, b| keep.contains(a) && keep.contains(b));
+        for name in keep {
+            if !graph.contains(name) {
+                *graph.add_node(name) = self.nodes[name].clone();
+            }
+        }
+
+        graph
+    }
+
+    /// Returns every caller mapped to its `(callee, call_count)` pairs, sorted by callee.
+    #[must_use]
+    pub fn to_weighted_adjacency_list(&self) -> BTreeMap<String, Vec<(String, u32)>> {
+        let mut list = BTreeMap::<String, Vec<(String, u32)>>::new();
+        for (caller, callee, count) in self.edges() {
+            list.entry(caller.to_owned())
+                .or_default()
+                .push((callee.to_owned(), count));
+        }
+        list
+    }
+
+    /// Returns every callee mapped to its `(caller, call_count)` pairs, sorted by caller.
+    #[must_use]
+    pub fn to_reverse_adjacency_list(&self) -> BTreeMap<String, Vec<(String, u32)>> {
+        let mut list = BTreeMap::<String, Vec<(String, u32)>>::new();
+        for (caller, callee, count) in self.edges() {
+            list.entry(callee.to_owned())
+                .or_default()
+                .push((caller.to_owned(), count));
+        }
+        list
+    }
+
+    /// Removes all nodes flagged `FunctionNode::is_coverage_counter`, returning how many were
+    /// removed.
+    pub fn remove_coverage_nodes(&mut self) -> usize {
+        let before = self.node_count();
+        self.retain_nodes(|n| !n.is_coverage_counter);
+        before - self.node_count()
+    }
+
+    /// All nodes for which `matches` returns true, in name order.
+    fn find_functions_where<F>(&self, matches: F) -> Vec<&FunctionNode>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.nodes().filter(|n| matches(&n.demangled)).collect()
+    }
+
+    /// All nodes whose demangled name matches `pattern`.
+    #[must_use]
+    pub fn find_functions_matching(&self, pattern: &Regex) -> Vec<&FunctionNode> {
+        self.find_functions_where(|name| pattern.is_match(name))
+    }
+
+    /// All nodes whose demangled name ends with `suffix`, e.g. `_new`.
+    #[must_use]
+    pub fn find_functions_with_suffix(&self, suffix: &str) -> Vec<&FunctionNode> {
+        self.find_functions_where(|name| name.ends_with(suffix))
+    }
+
+    /// All nodes whose demangled name starts with `prefix`.
+    #[must_use]
+    pub fn find_functions_with_prefix(&self, prefix: &str) -> Vec<&FunctionNode> {
+        self.find_functions_where(|name| name.starts_with(prefix))
+    }
+
+    /// All nodes with a parameter whose LLVM type starts with `type_prefix`, e.g. `ptr` for raw
+    /// pointers and references.
+    #[must_use]
+    pub fn find_functions_with_parameter_type(&self, type_prefix: &str) -> Vec<&FunctionNode> {
+        self.nodes()
+            .filter(|n| n.parameter_types.iter().any(|t| t.starts_with(type_prefix)))
+            .collect()
+    }
+
+    /// All nodes whose demangled path contains the `::` separated `module_path` as contiguous
+    /// segments, so `fs::read` matches `std::fs::read::inner` but not `std::fs::read_dir`.
+    #[must_use]
+    pub fn find_functions_in_module(&self, module_path: &str) -> Vec<&FunctionNode> {
+        let module: Vec<_> = module_path.split("::").collect();
+        self.find_functions_where(|name| {
+            let segments: Vec<_> = name.split("::").collect();
+            segments
+                .windows(module.len())
+                .any(|w| w == module.as_slice())
+        })
+    }
+
+    /// Enumerates the simple call paths (no repeated functions) from `entry` to a panic function,
+    /// see `is_panic_function`, with at most `max_depth` calls, stopping once `max_paths` have
+    /// been found. Each path runs from `entry` to the panic function inclusive and ends at the
+    /// first panic function reached. Paths are found by depth-first search and returned in
+    /// lexicographic order. The graph must be extracted with `AnalysisConfig::include_panic_sites`
+    /// for panic functions to be present.
+    #[must_use]
+    pub fn detect_panic_paths_from(
+        &self,
+        entry: &str,
+        max_depth: usize,
+        max_paths: usize,
+    ) -> Vec<Vec<String>> {
+        fn visit<'a>(
+            graph: &'a CallGraph,
+            limits: (usize, usize),
+            path: &mut Vec<&'a str>,
+            paths: &mut Vec<Vec<String>>,
+        ) {
+            let (max_depth, max_paths) = limits;
+            let current = path[path.len() - 1];
+            if is_panic_function(current) {
+                paths.push(path.iter().map(|n| (*n).to_owned()).collect());
+                return;
+            }
+            if path.len() > max_depth {
+                return;
+            }
+
+            for callee in graph.callees(current) {
+                if paths.len() >= max_paths {
+                    return;
+                }
+                if !path.contains(&callee) {
+                    path.push(callee);
+                    visit(graph, limits, path, paths);
+                    path.pop();
+                }
+            }
+        }
+
+        let mut paths = Vec::new();
+        if let Some((start, _)) = self.nodes.get_key_value(entry) {
+            if max_paths > 0 {
+                visit(
+                    self,
+                    (max_depth, max_paths),
+                    &mut vec![start.as_str()],
+                    &mut paths,
+                );
+            }
+        }
+
+        paths
+    }
+
+    /// Collapses the graph into a module dependency graph, where each node is the `module_path`
+    /// of one or more functions and `A -> B` means some function in `A` calls one in `B`, with
+    /// the call counts summed. Calls within a module are dropped, but every module is kept as a
+    /// node.
+    #[must_use]
+    pub fn compute_module_dependency_graph(&self) -> CallGraph {
+        let mut graph = CallGraph::new();
+
+        for node in self.nodes() {
+            graph.add_node(module_path(&node.demangled));
+        }
+        for (caller, callee, count) in self.edges() {
+            let (from, to) = (module_path(caller), module_path(callee));
+            if from != to {
+                graph.add_edge_with_count(from, to, count);
+            }
+        }
+
+        graph
+    }
+
+    /// Computes a small set of edges whose removal makes the graph acyclic, using the greedy
+    /// heuristic of Eades, Lin and Smyth: sinks are repeatedly moved to the end of an ordering
+    /// and sources to its front, otherwise the node with the largest out-degree minus in-degree
+    /// goes to the front. Every edge pointing backwards in the ordering, including self calls, is
+    /// part of the returned set.
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn compute_feedback_arc_set(&self) -> Vec<(String, String)> {
+        let mut remaining: BTreeSet<&str> = self.nodes.keys().map(String::as_str).collect();
+        let mut out_degree: HashMap<&str, usize> = HashMap::new();
+        let mut in_degree: HashMap<&str, usize> = HashMap::new();
+        for (caller, callee, _) in self.edges().filter(|(a, b, _)| a != b) {
+            *out_degree.entry(caller).or_default() += 1;
+            *in_degree.entry(callee).or_default() += 1;
+        }
+
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        while !remaining.is_empty() {
+            let next = remaining
+                .iter()
+                .find(|n| out_degree.get(*n).copied().unwrap_or(0) == 0)
+                .map(|n| (*n, false))
+                .or_else(|| {
+                    remaining
+                        .iter()
+                        .find(|n| in_degree.get(*n).copied().unwrap_or(0) == 0)
+                        .map(|n| (*n, true))
+                })
+                .or_else(|| {
+                    remaining
+                        .iter()
+                        .max_by_key(|n| {
+                            let out = out_degree.get(*n).copied().unwrap_or(0);
+                            let into = in_degree.get(*n).copied().unwrap_or(0);
+                            (out as isize - into as isize, std::cmp::Reverse(**n))
+                        })
+                        .map(|n| (*n, true))
+                });
+            let Some((node, to_front)) = next else {
+                break;
+            };
+
+            remaining.remove(node);
+            for callee in self.callees(node).filter(|c| remaining.contains(c)) {
+                if let Some(degree) = in_degree.get_mut(callee) {
+                    *degree -= 1;
+                }
+            }
+            for caller in self.callers(node).filter(|c| remaining.contains(c)) {
+                if let Some(degree) = out_degree.get_mut(caller) {
+                    *degree -= 1;
+                }
+            }
+
+            if to_front {
+                front.push(node);
+            } else {
+                back.push(node);
+            }
+        }
+
+        let position: HashMap<&str, usize> = front
+            .into_iter()
+            .chain(back.into_iter().rev())
+            .enumerate()
+            .map(|(i, n)| (n, i))
+            .collect();
+
+        self.edges()
+            .filter(|(caller, callee, _)| position[caller] >= position[callee])
+            .map(|(caller, callee, _)| (caller.to_owned(), callee.to_owned()))
+            .collect()
+    }
+
+    /// Returns an acyclic copy of the graph with the edges of `compute_feedback_arc_set`
+    /// removed, along with the removed edges. All nodes are kept.
+    #[must_use]
+    pub fn without_feedback_arcs(&self) -> (CallGraph, Vec<(String, String)>) {
+        let arcs = self.compute_feedback_arc_set();
+        let removed: HashSet<(&str, &str)> =
+            arcs.iter().map(|(a, b)| (a.as_str(), b.as_str())).collect();
+
+        let mut graph = self.filter_edges(|a, b| !removed.contains(&(a, b)));
+        for node in self.nodes() {
+            if !graph.contains(&node.demangled) {
+                *graph.add_node(&node.demangled) = node.clone();
+            }
+        }
+
+        (graph, arcs)
+    }
+}
+impl<S1, S2> FromIterator<(S1, S2)> for CallGraph
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    fn from_iter<I: IntoIterator<Item = (S1, S2)>>(iter: I) -> Self {
+        let mut graph = Self::new();
+        iter.into_iter()
+            .for_each(|(caller, callee)| graph.add_edge(caller, callee));
+        graph
+    }
+}
+impl From<CallGraph> for SerializedGraph {
+    fn from(graph: CallGraph) -> Self {
+        let edges = graph
+            .edges()
+            .map(|(caller, callee, count)| SerializedEdge {
+                caller: caller.to_owned(),
+                callee: callee.to_owned(),
+                count,
+                synthetic: graph.is_synthetic_edge(caller, callee),
+            })
+            .collect();
+
+        Self {
+            nodes: graph.nodes.into_values().collect(),
+            edges,
+        }
+    }
+}
+impl From<SerializedGraph> for CallGraph {
+    fn from(serialized: SerializedGraph) -> Self {
+        let mut graph = Self::new();
+
+        for node in serialized.nodes {
+            let name = node.demangled.clone();
+            *graph.add_node(name) = node;
+        }
+        for edge in serialized.edges {
+            graph.add_edge_with_count(&edge.caller, &edge.callee, edge.count);
+            if edge.synthetic {
+                graph.synthetic_edges.insert((edge.caller, edge.callee));
+            }
+        }
+
+        graph
+    }
+}
+
+/// State of the search for the circuits through `start` in `CallGraph::find_all_cycles`.
+struct CircuitSearch<'a, 'c> {
+    graph: &'a CallGraph,
+    component: &'c HashSet<&'a str>,
+    start: &'a str,
+    /// `(max_length, max_cycles)`.
+    limits: (usize, usize),
+    path: Vec<&'a str>,
+    blocked: HashSet<&'a str>,
+    /// Johnson's B lists: the blocked functions to unblock along with each function.
+    blocked_by: HashMap<&'a str, HashSet<&'a str>>,
+    cycles: &'c mut Vec<Vec<String>>,
+}
+impl<'a> CircuitSearch<'a, '_> {
+    /// Extends the path by `v`, returning whether a cycle was closed, the search was cut short
+    /// or the cycle limit reached below it, in which case `v` is unblocked again.
+    fn circuit(&mut self, v: &'a str) -> bool {
+        let (max_length, max_cycles) = self.limits;
+        self.path.push(v);
+        self.blocked.insert(v);
+
+        let mut closed = false;
+        let callees: Vec<&'a str> = self
+            .graph
+            .callees(v)
+            .filter(|w| *w >= self.start && self.component.contains(w))
+            .collect();
+        for &w in &callees {
+            if self.cycles.len() >= max_cycles {
+                closed = true;
+                break;
+            }
+            if w == self.start {
+                self.cycles
+                    .push(self.path.iter().map(|n| (*n).to_owned()).collect());
+                closed = true;
+            } else if !self.blocked.contains(w) {
+                closed |= self.path.len() >= max_length || self.circuit(w);
+            }
+        }
+
+        if closed {
+            self.unblock(v);
+        } else {
+            for w in callees {
+                self.blocked_by.entry(w).or_default().insert(v);
+            }
+        }
+        self.path.pop();
+
+        closed
+    }
+
+    fn unblock(&mut self, v: &'a str) {
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            if self.blocked.remove(u) {
+                stack.extend(self.blocked_by.remove(u).into_iter().flatten());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate test;
+
+    #[test]
+    fn sanitizer_runtime_functions_are_hooks() {
+        for hook in [
+            "__asan_report_load8",
+            "__msan_warning",
+            "__sanitizer_cov_trace_pc",
+        ] {
+            assert!(FunctionNode::new(hook).is_sanitizer_hook, "{hook}");
+        }
+        for name in ["app::main", "memcpy", "app::__asan_report"] {
+            assert!(!FunctionNode::new(name).is_sanitizer_hook, "{name}");
+        }
+    }
+
+    #[test]
+    fn edge_counts_accumulate_and_saturate() {
+        let mut graph = CallGraph::default();
+        graph.add_edge_with_count("a::f", "b::g", 2);
+        graph.add_edge("a::f", "b::g");
+        assert_eq!(graph.call_count("a::f", "b::g"), Some(3));
+        assert_eq!(graph.inter_crate_edges, 1);
+
+        graph.add_edge_with_count("a::f", "b::g", u32::MAX);
+        assert_eq!(graph.call_count("a::f", "b::g"), Some(u32::MAX));
+        assert_eq!(graph.inter_crate_edges, 1);
+    }
+
+    #[test]
+    fn cycles_of_complete_graph() {
+        // Every pair of the four functions calls each other.
+        let names = ["a", "b", "c", "d"];
+        let graph: CallGraph = names
+            .iter()
+            .flat_map(|a| names.iter().filter(move |b| a != *b).map(move |b| (*a, *b)))
+            .collect();
+
+        let cycles = graph.find_all_cycles(4, usize::MAX);
+        assert_eq!(cycles.iter().filter(|c| c.len() == 2).count(), 6);
+
+        // Each cycle through three or more functions of K4 is found once in each direction.
+        let long: Vec<_> = cycles.iter().filter(|c| c.len() >= 3).collect();
+        assert_eq!(long.len(), 14);
+        assert_eq!(long.iter().filter(|c| c[1] < c[c.len() - 1]).count(), 7);
+
+        assert_eq!(graph.find_all_cycles(3, usize::MAX).len(), 14);
+        assert_eq!(graph.find_all_cycles(4, 5).len(), 5);
+    }
+
+    #[test]
+    fn johnson_matches_plain_backtracking() {
+        fn backtrack<'a>(graph: &'a CallGraph, max: usize, path: &mut Vec<&'a str>) -> usize {
+            let mut found = 0;
+            for callee in graph.callees(path[path.len() - 1]) {
+                if callee == path[0] {
+                    found += 1;
+                } else if callee > path[0] && path.len() < max && !path.contains(&callee) {
+                    path.push(callee);
+                    found += backtrack(graph, max, path);
+                    path.pop();
+                }
+            }
+            found
+        }
+
+        let names: Vec<String> = (0..9).map(|i| format!("f{i}")).collect();
+        let graph: CallGraph = (0..9)
+            .flat_map(|i| [(i, (i + 1) % 9), (i, (i * 4 + 2) % 9), (i, (i + 7) % 9)])
+            .map(|(a, b)| (names[a].as_str(), names[b].as_str()))
+            .collect();
+
+        for max_length in 1..=9 {
+            let expected: usize = names
+                .iter()
+                .map(|n| backtrack(&graph, max_length, &mut vec![n.as_str()]))
+                .sum();
+            let cycles = graph.find_all_cycles(max_length, usize::MAX);
+            assert_eq!(cycles.len(), expected, "max_length {max_length}");
+            assert!(cycles.iter().all(|c| c.len() <= max_length));
+            let unique: HashSet<_> = cycles.iter().collect();
+            assert_eq!(unique.len(), cycles.len());
+        }
+    }
+
+    #[test]
+    fn panic_paths_cover_every_route() {
+        let graph: CallGraph = [
+            ("app::main", "app::parse"),
+            ("app::main", "app::run"),
+            ("app::parse", "core::panicking::panic"),
+            ("app::run", "app::parse"),
+            ("app::run", "core::result::unwrap_failed"),
+            ("core::panicking::panic", "core::panicking::panic_fmt"),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            graph.detect_panic_paths_from("app::main", 8, usize::MAX),
+            [
+                vec!["app::main", "app::parse", "core::panicking::panic"],
+                vec![
+                    "app::main",
+                    "app::run",
+                    "app::parse",
+                    "core::panicking::panic"
+                ],
+                vec!["app::main", "app::run", "core::result::unwrap_failed"],
+            ]
+        );
+        assert_eq!(
+            graph
+                .detect_panic_paths_from("app::main", 2, usize::MAX)
+                .len(),
+            2
+        );
+        assert_eq!(graph.detect_panic_paths_from("app::main", 8, 1).len(), 1);
+    }
+
+    #[test]
+    fn removing_feedback_arcs_leaves_graph_acyclic() {
+        let graph: CallGraph = [
+            ("a", "b"),
+            ("b", "c"),
+            ("c", "a"),
+            ("c", "d"),
+            ("d", "b"),
+            ("d", "d"),
+            ("d", "e"),
+        ]
+        .into_iter()
+        .collect();
+        assert!(!graph.find_cycles().is_empty());
+
+        let (acyclic, arcs) = graph.without_feedback_arcs();
+        assert!(acyclic.find_cycles().is_empty());
+        assert!(arcs.contains(&("d".to_owned(), "d".to_owned())));
+        assert_eq!(acyclic.node_count(), graph.node_count());
+        assert_eq!(acyclic.edge_count() + arcs.len(), graph.edge_count());
+    }
+
+    #[test]
+    fn bridges_of_chain_and_two_cycle() {
+        let chain: CallGraph = [("a", "b"), ("b", "c"), ("c", "d")].into_iter().collect();
+        let bridges: Vec<_> = chain
+            .edges()
+            .map(|(a, b, _)| (a.to_owned(), b.to_owned()))
+            .collect();
+        assert_eq!(chain.find_bridge_edges(), bridges);
+
+        // A link called twice is still a single edge, so it remains a bridge.
+        let mut repeated = chain.clone();
+        repeated.add_edge_with_count("d", "e", 2);
+        let mut bridges = bridges;
+        bridges.push(("d".to_owned(), "e".to_owned()));
+        assert_eq!(repeated.find_bridge_edges(), bridges);
+
+        let two_cycle: CallGraph = [("a", "b"), ("b", "a")].into_iter().collect();
+        assert!(two_cycle.find_bridge_edges().is_empty());
+    }
+
+    #[test]
+    fn trait_impl_methods_belong_to_their_self_type() {
+        assert_eq!(
+            module_path("<app::io::Foo as core::fmt::Debug>::fmt"),
+            "app::io::Foo"
+        );
+        assert_eq!(
+            module_path("<&mut app::Foo<T> as core::ops::Drop>::drop"),
+            "app::Foo"
+        );
+        assert_eq!(module_path("<app::Foo>::new"), "app::Foo");
+        assert_eq!(
+            module_path("<<app::A as app::Tr>::Out as core::Tr>::f"),
+            "app::A"
+        );
+
+        let graph: CallGraph = [
+            (
+                "<app::io::Foo as core::fmt::Debug>::fmt",
+                "core::fmt::write",
+            ),
+            ("app::main", "<app::io::Foo as core::fmt::Debug>::fmt"),
+        ]
+        .into_iter()
+        .collect();
+        let coupling = graph.compute_afferent_efferent_coupling();
+        assert_eq!(coupling["app::io"], (1, 1));
+        assert_eq!(coupling["core::fmt"], (1, 0));
+        assert_eq!(coupling["app"], (0, 1));
+        assert_eq!(coupling.len(), 3);
+    }
+
+    #[test]
+    fn module_graph_merges_trait_impls_into_self_type() {
+        let graph: CallGraph = [
+            ("app::main", "<app::Foo as core::fmt::Display>::fmt"),
+            ("app::main", "app::Foo::new"),
+            ("<app::Foo as core::fmt::Display>::fmt", "app::Foo::name"),
+            ("<app::Foo as core::fmt::Display>::fmt", "core::fmt::write"),
+        ]
+        .into_iter()
+        .collect();
+
+        let modules = graph.compute_module_dependency_graph();
+        let mut names: Vec<_> = modules
+            .nodes()
+            .map(|node| node.demangled.as_str())
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["app", "app::Foo", "core::fmt"]);
+        assert_eq!(modules.call_count("app", "app::Foo"), Some(2));
+        assert_eq!(modules.call_count("app::Foo", "core::fmt"), Some(1));
+        assert_eq!(modules.edge_count(), 2);
+    }
+
+    #[test]
+    fn adjacency_lists_flatten_to_edges() {
+        let mut graph: CallGraph = [
+            ("app::main", "app::run"),
+            ("app::main", "app::log"),
+            ("app::run", "app::log"),
+            ("app::run", "app::run"),
+            ("app::log", "std::io::write"),
+        ]
+        .into_iter()
+        .collect();
+        graph.add_edge_with_count("app::main", "app::run", 3);
+        let edges: Vec<_> = graph
+            .edges()
+            .map(|(caller, callee, count)| (caller.to_owned(), callee.to_owned(), count))
+            .collect();
+
+        let forward: Vec<_> = graph
+            .to_weighted_adjacency_list()
+            .into_iter()
+            .flat_map(|(caller, callees)| {
+                callees
+                    .into_iter()
+                    .map(move |(callee, count)| (caller.clone(), callee, count))
+            })
+            .collect();
+        assert_eq!(forward, edges);
+
+        let mut reverse: Vec<_> = graph
+            .to_reverse_adjacency_list()
+            .into_iter()
+            .flat_map(|(callee, callers)| {
+                callers
+                    .into_iter()
+                    .map(move |(caller, count)| (caller, callee.clone(), count))
+            })
+            .collect();
+        reverse.sort();
+        assert_eq!(reverse, edges);
+    }
+
+    #[test]
+    fn single_choke_point_is_critical() {
+        let graph: CallGraph = [
+            ("app::main", "app::a"),
+            ("app::main", "app::b"),
+            ("app::a", "app::choke"),
+            ("app::b", "app::choke"),
+            ("app::cli", "app::choke"),
+            ("app::choke", "app::x"),
+            ("app::choke", "app::y"),
+        ]
+        .into_iter()
+        .collect();
+
+        let expected = HashSet::from(["app::choke".to_owned()]);
+        assert_eq!(graph.compute_critical_functions(&["app::main"]), expected);
+        assert_eq!(
+            graph.compute_critical_functions(&["app::main", "app::cli"]),
+            expected
+        );
+    }
+
+    #[test]
+    fn eccentricity_of_eight_node_chain() {
+        let names: Vec<String> = (0..8).map(|i| format!("n{i}")).collect();
+        let graph: CallGraph = names.windows(2).map(|w| (&w[0], &w[1])).collect();
+
+        let eccentricity = graph.compute_node_eccentricity();
+        for (i, name) in names.iter().enumerate().take(7) {
+            assert_eq!(eccentricity[name], 7 - i);
+        }
+        assert_eq!(eccentricity["n7"], usize::MAX);
+        assert_eq!(graph.radius(), 1);
+        assert_eq!(graph.diameter(), 7);
+    }
+
+    #[test]
+    fn entropy_of_degree_distributions() {
+        // Every function of a cycle calls and is called by exactly one other.
+        let uniform: CallGraph = [("a", "b"), ("b", "c"), ("c", "d"), ("d", "a")]
+            .into_iter()
+            .collect();
+        assert!(uniform.compute_graph_entropy().abs() < 1e-12);
+        assert!(uniform.compute_in_degree_entropy().abs() < 1e-12);
+
+        // Out-degrees 3, 2, 1 and 0 are equally likely, so two bits.
+        let varied: CallGraph = [
+            ("a", "b"),
+            ("a", "c"),
+            ("a", "d"),
+            ("b", "c"),
+            ("b", "d"),
+            ("c", "d"),
+        ]
+        .into_iter()
+        .collect();
+        assert!((varied.compute_graph_entropy() - 2.0).abs() < 1e-12);
+        assert!((varied.compute_in_degree_entropy() - 2.0).abs() < 1e-12);
+    }
+
+    /// `count` overlapping graphs, each a chain through a shared crate with its own annotations.
+    fn corpus(count: usize) -> Vec<CallGraph> {
+        (0..count)
+            .map(|i| {
+                let mut graph = CallGraph::new();
+                for j in 0..50 {
+                    graph.add_edge(format!("crate{i}::f{j}"), format!("crate{i}::f{}", j + 1));
+                    graph.add_edge(
+                        format!("crate{i}::f{j}"),
+                        format!("shared::g{}", (i + j) % 20),
+                    );
+                }
+                graph.node_mut("shared::g0").unwrap().execution_count = Some(i as u64);
+                graph
+                    .add_synthetic_edge(&format!("crate{i}::f0"), "shared::callback")
+                    .unwrap();
+                graph
+            })
+            .collect()
+    }
+
+    fn merge_sequential(graphs: Vec<CallGraph>) -> CallGraph {
+        graphs
+            .into_iter()
+            .fold(CallGraph::new(), |mut merged, graph| {
+                merged.merge(graph);
+                merged
+            })
+    }
+
+    #[test]
+    fn parallel_merge_equals_sequential_merge() {
+        let parallel = CallGraph::merge_parallel(corpus(100));
+        let sequential = merge_sequential(corpus(100));
+
+        assert!(parallel.nodes().eq(sequential.nodes()));
+        assert!(parallel.edges().eq(sequential.edges()));
+        assert_eq!(parallel.synthetic_edges, sequential.synthetic_edges);
+        assert_eq!(parallel.inter_crate_edges, sequential.inter_crate_edges);
+    }
+
+    #[bench]
+    fn bench_merge_sequential(b: &mut test::Bencher) {
+        let graphs = corpus(100);
+        b.iter(|| merge_sequential(graphs.clone()));
+    }
+
+    #[bench]
+    fn bench_merge_parallel(b: &mut test::Bencher) {
+        let graphs = corpus(100);
+        b.iter(|| CallGraph::merge_parallel(graphs.clone()));
+    }
+
+    #[test]
+    fn dominance_frontier_of_diamond() {
+        let graph: CallGraph = [("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]
+            .into_iter()
+            .collect();
+
+        let frontier = graph.compute_function_dominance_frontier("a");
+        let d = HashSet::from(["d".to_owned()]);
+        assert_eq!(frontier["b"], d);
+        assert_eq!(frontier["c"], d);
+        assert!(frontier["a"].is_empty());
+        assert!(frontier["d"].is_empty());
+        assert!(graph.compute_function_dominance_frontier("x").is_empty());
+    }
+
+    #[test]
+    fn api_stability_against_itself_and_a_change() {
+        let graph: CallGraph = [
+            ("app::open", "app::read"),
+            ("app::open", "app::parse"),
+            ("app::close", "app::flush"),
+        ]
+        .into_iter()
+        .collect();
+        assert!((graph.compute_api_stability_score(&graph) - 1.0).abs() < 1e-12);
+
+        // `app::open` keeps one of its two callees and `app::close` is gone.
+        let next: CallGraph = [("app::open", "app::read")].into_iter().collect();
+        assert!((next.compute_api_stability_score(&graph) - 0.25).abs() < 1e-12);
+    }
+}
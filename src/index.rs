@@ -65,7 +65,7 @@ pub async fn update_missing_crates(conn: Arc<Db>) -> Result<(), Error> {
                 println!("Missing crate: {}", c.name());
                 if let Err(e) = insert_fresh_crate(c.clone(), db.clone()).await {
                     log::error!("Failed crate: {}", c.name());
-                    log::error!("Failed crate: {}", e);
+                    log::error!("Failed crate: {e}");
                 }
             }
         }
@@ -145,7 +145,9 @@ pub async fn set_latest_versions(conn: Arc<Db>) -> Result<(), Error> {
 
     let do_crate = |c: Crate, db: Arc<Db>| async move {
         let latest = c.highest_version();
-        db.set_latest(c.name(), latest.version()).await;
+        if let Err(e) = db.set_latest(c.name(), latest.version()).await {
+            log::error!("Setting latest version of {} failed: {e}", c.name());
+        }
     };
 
     let iter = index.crates().array_chunks::<64>();
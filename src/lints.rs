@@ -0,0 +1,209 @@
+use crate::{
+    graph::{is_extern_c, CallGraph},
+    Error,
+};
+use std::path::Path;
+
+/// A clippy warning reduced to its lint name and primary span location.
+struct LintWarning {
+    lint: String,
+    file: String,
+    line: u32,
+}
+
+/// Runs `cargo clippy --message-format=json` in `src_path`, returning every warning with a lint
+/// code and a primary span.
+fn clippy_warnings(src_path: &Path) -> Result<Vec<LintWarning>, Error> {
+    let output = std::process::Command::new("cargo")
+        .args(["clippy", "--message-format=json"])
+        .current_dir(src_path)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::ClippyError(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter_map(|msg| {
+            if msg.get("reason")?.as_str()? != "compiler-message" {
+                return None;
+            }
+
+            let message = msg.get("message")?;
+            if message.get("level")?.as_str()? != "warning" {
+                return None;
+            }
+
+            let lint = message.get("code")?.get("code")?.as_str()?.to_owned();
+            let span =
+                message.get("spans")?.as_array()?.iter().find(|s| {
+                    s.get("is_primary").and_then(serde_json::Value::as_bool) == Some(true)
+                })?;
+
+            Some(LintWarning {
+                lint,
+                file: span.get("file_name")?.as_str()?.to_owned(),
+                line: u32::try_from(span.get("line_start")?.as_u64()?).ok()?,
+            })
+        })
+        .collect())
+}
+
+/// Runs clippy on the crate at `src_path` and records the name of every warning's lint in
+/// `FunctionNode::clippy_warnings` of the function containing it. Functions are located through
+/// `FunctionNode::location`: a warning belongs to the function in the same file which starts
+/// closest before it. Nodes without a location are never annotated.
+///
+/// # Errors
+/// Returns `Error::ClippyError` if clippy fails, and `Error::IoError` if cargo could not be
+/// executed.
+pub fn annotate_with_clippy_lints(graph: &mut CallGraph, src_path: &Path) -> Result<(), Error> {
+    for warning in clippy_warnings(src_path)? {
+        let owner = graph
+            .nodes()
+            .filter_map(|n| Some((n, n.location.as_ref()?)))
+            .filter(|(_, loc)| {
+                Path::new(&loc.file).ends_with(&warning.file) && loc.line <= warning.line
+            })
+            .max_by_key(|(_, loc)| loc.line)
+            .map(|(n, _)| n.demangled.clone());
+
+        if let Some(node) = owner.and_then(|name| graph.node_mut(&name)) {
+            if !node.clippy_warnings.contains(&warning.lint) {
+                node.clippy_warnings.push(warning.lint);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// How serious a `LintFinding` is.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// A single problem reported by a `LintRule`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LintFinding {
+    /// `LintRule::name` of the rule which reported it.
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    /// Demangled names of the functions involved.
+    pub nodes: Vec<String>,
+}
+
+/// A check over a whole `CallGraph`, run by `lint_call_graph`.
+pub trait LintRule: Send + Sync {
+    fn name(&self) -> &str;
+    fn check(&self, graph: &CallGraph) -> Vec<LintFinding>;
+}
+
+/// Reports an error for every cycle, see `CallGraph::find_cycles`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoCyclesRule;
+impl LintRule for NoCyclesRule {
+    fn name(&self) -> &'static str {
+        "no_cycles"
+    }
+
+    fn check(&self, graph: &CallGraph) -> Vec<LintFinding> {
+        graph
+            .find_cycles()
+            .into_iter()
+            .map(|cycle| LintFinding {
+                rule: self.name().to_owned(),
+                severity: Severity::Error,
+                message: format!("{} functions form a call cycle", cycle.len()),
+                nodes: cycle,
+            })
+            .collect()
+    }
+}
+
+/// Reports a warning for every function calling more than the given number of functions.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxOutDegreeRule(pub usize);
+impl LintRule for MaxOutDegreeRule {
+    fn name(&self) -> &'static str {
+        "max_out_degree"
+    }
+
+    fn check(&self, graph: &CallGraph) -> Vec<LintFinding> {
+        graph
+            .nodes()
+            .map(|n| (n, graph.out_degree(&n.demangled)))
+            .filter(|(_, degree)| *degree > self.0)
+            .map(|(n, degree)| LintFinding {
+                rule: self.name().to_owned(),
+                severity: Severity::Warning,
+                message: format!(
+                    "calls {degree} functions, more than the limit of {}",
+                    self.0
+                ),
+                nodes: vec![n.demangled.clone()],
+            })
+            .collect()
+    }
+}
+
+/// Reports an error for every call to an unmangled symbol, i.e. a function without a Rust path
+/// such as one declared in an `extern "C"` block.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoExternCCallsRule;
+impl LintRule for NoExternCCallsRule {
+    fn name(&self) -> &'static str {
+        "no_extern_c_calls"
+    }
+
+    fn check(&self, graph: &CallGraph) -> Vec<LintFinding> {
+        graph
+            .edges()
+            .filter(|(_, callee, _)| is_extern_c(callee))
+            .map(|(caller, callee, _)| LintFinding {
+                rule: self.name().to_owned(),
+                severity: Severity::Error,
+                message: format!("calls foreign function {callee}"),
+                nodes: vec![caller.to_owned(), callee.to_owned()],
+            })
+            .collect()
+    }
+}
+
+/// Reports an error for every call to an unsafe function, see `FunctionNode::is_unsafe`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoUnsafeCallsRule;
+impl LintRule for NoUnsafeCallsRule {
+    fn name(&self) -> &'static str {
+        "no_unsafe_calls"
+    }
+
+    fn check(&self, graph: &CallGraph) -> Vec<LintFinding> {
+        graph
+            .edges()
+            .filter(|(_, callee, _)| graph.node(callee).is_some_and(|n| n.is_unsafe))
+            .map(|(caller, callee, _)| LintFinding {
+                rule: self.name().to_owned(),
+                severity: Severity::Error,
+                message: format!("calls unsafe function {callee}"),
+                nodes: vec![caller.to_owned(), callee.to_owned()],
+            })
+            .collect()
+    }
+}
+
+/// Runs every rule over `graph`, returning their findings in rule order.
+#[must_use]
+pub fn lint_call_graph(graph: &CallGraph, rules: &[Box<dyn LintRule>]) -> Vec<LintFinding> {
+    rules.iter().flat_map(|rule| rule.check(graph)).collect()
+}
@@ -1,27 +1,19 @@
 #![deny(clippy::all, clippy::pedantic)]
 #![allow(clippy::enum_variant_names)]
-#![feature(string_remove_matches)]
-#![feature(iter_array_chunks)]
-
-mod analysis;
-mod compile;
-mod crate_fs;
-mod db;
-mod index;
-
-mod error;
 
 use clap::{Parser, Subcommand};
-use crate_fs::{CrateFs, CrateFsConfig};
-use db::Db;
+use libpainter::{
+    analysis, compile, config,
+    crate_fs::{CrateFs, CrateFsConfig},
+    db::Db,
+    export, index, policy, Error, Roots,
+};
 use rayon::prelude::*;
 use std::{
     path::PathBuf,
     sync::{Arc, Mutex},
 };
 
-pub use error::Error;
-
 /// Top level arguments
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -31,24 +23,11 @@ struct Args {
     command: Command,
 }
 
-/// Clap argument object for specifying the root paths to be used for this work session.
-/// These folders are as follows:
-/// `sources_root`: A location that all crate sources to be analyzed have been extracted to in the format of
-/// `sources_root/<name>-<version>`
-/// `bytecodes_root`: A location that all bytecodes will be emitted via `rustc`, distributed in folders
-/// in the format of `sources_root/<name>-<version>`
-#[derive(clap::Args, Debug, Clone)]
-struct Roots {
-    /// Root directory containing the extracted sources tree.
-    #[arg(short = 's', value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
-    pub sources_root: PathBuf,
-    /// Root directory containing bytecodes, matching the name-version layout of the source tree.
-    /// Can be the same root path to output bytecode artifacts into the source tree.
-    #[arg(short = 'b', value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
-    pub bytecodes_root: Option<PathBuf>,
-
-    #[arg(short = 'c', value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
-    pub compressed_root: PathBuf,
+/// Reports the `graph` command can print in place of the call graph.
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+enum Report {
+    /// Array of the functions not reachable from any entry point.
+    DeadCode,
 }
 
 /// Command stages of painter to execute.
@@ -62,10 +41,19 @@ enum Command {
         #[command(flatten)]
         roots: Roots,
     },
+    /// Compile every crate listed in a batch manifest, printing the result of each.
+    BatchCompile {
+        /// TOML manifest of `[[crates]]` tables with `name`, `version` and `src_path` keys.
+        #[arg(long = "batch-manifest", value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        manifest: PathBuf,
+    },
     /// Compile all crates found within the source tree.
     CompileAll {
         #[arg(long, short = 'u', default_value = "true")]
         update_only: bool,
+        /// Toolchain to compile with, overriding `default_toolchain` from the user config.
+        #[arg(long)]
+        toolchain: Option<String>,
         #[command(flatten)]
         roots: Roots,
     },
@@ -80,7 +68,71 @@ enum Command {
         #[command(flatten)]
         roots: Roots,
     },
+    /// Export the calls of all crates with built bytecode to a `calls.csv` in each bytecode
+    /// directory
+    ExportAllCsv {
+        #[command(flatten)]
+        roots: Roots,
+    },
     SemverCheck,
+    /// Audit the call graph of a single crates bytecode against a policy, printing the report as
+    /// JSON.
+    Audit {
+        /// Directory containing the crates bytecode.
+        #[arg(short = 'b', value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+        crate_bc_dir: PathBuf,
+        /// Regex of a callee which must never be invoked. May be repeated.
+        #[arg(long = "ban")]
+        banned: Vec<String>,
+        /// Regex which must not match any function. May be repeated.
+        #[arg(long = "forbid")]
+        forbidden: Vec<String>,
+        /// Largest allowed fraction of unsafe functions, between 0.0 and 1.0.
+        #[arg(long = "max-unsafe-ratio", value_name = "RATIO")]
+        max_unsafe_ratio: Option<f64>,
+        /// Largest allowed number of calls into extern C functions.
+        #[arg(long = "max-extern-c-edges", value_name = "N")]
+        max_extern_c_edges: Option<usize>,
+        /// Function name prefix to drop from the graph, replacing `extra_blocked_prefixes` from
+        /// the user config. May be repeated.
+        #[arg(long = "block-prefix")]
+        blocked_prefixes: Vec<String>,
+    },
+    /// Write the call graph of a single crates bytecode to stdout.
+    Graph {
+        /// Directory containing the crates bytecode.
+        #[arg(
+            short = 'b',
+            value_name = "DIR",
+            value_hint = clap::ValueHint::DirPath,
+            required_unless_present = "print_schema"
+        )]
+        crate_bc_dir: Option<PathBuf>,
+        /// Format to write, overriding `default_output_format` from the user config.
+        #[arg(long = "output", value_enum)]
+        format: Option<config::OutputFormat>,
+        /// Print the JSON Schema of the `json` output format instead of a graph.
+        #[arg(long)]
+        print_schema: bool,
+        /// Print a report on the graph as JSON instead of the graph itself.
+        #[arg(long, value_enum)]
+        report: Option<Report>,
+        /// Entry point of the `dead-code` report. May be repeated; defaults to every function
+        /// without callers.
+        #[arg(long = "entry", value_name = "FUNCTION")]
+        entry_points: Vec<String>,
+    },
+    /// Check that the tools needed to compile crates are installed.
+    Doctor {
+        /// Toolchain to check for, overriding `default_toolchain` from the user config.
+        #[arg(long)]
+        toolchain: Option<String>,
+    },
+    /// Manage the user config file at `~/.config/painter/config.toml`.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
 
     // Database operations
     CreateFreshDb {
@@ -121,31 +173,38 @@ enum Command {
     },
 }
 
-/// Container object for storing the information of a given crate.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct CrateSource {
-    /// The crates name
-    name: String,
-    /// The crates semver version in `String` format.
-    version: String,
-    /// The fully qualified path the crate source was discovered and is located.
-    path: PathBuf,
+/// Subcommands of `painter config`.
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Write a commented config template, unless a config already exists.
+    Init,
 }
 
-fn cratefs_from_roots(roots: &Roots) -> Result<CrateFs, Error> {
+fn cratefs_from_roots(roots: &Roots) -> CrateFs {
     // Queue up the caching FS
-    Ok(CrateFs::new(CrateFsConfig::with_paths(
+    CrateFs::new(CrateFsConfig::with_paths(
         roots.compressed_root.clone(),
         roots.sources_root.clone(),
-    ))?)
+    ))
+}
+
+/// The toolchain given on the command line, otherwise `default_toolchain` from the user config,
+/// otherwise that of `CompileOptions::default`. The user config is only read when needed.
+fn resolve_toolchain(flag: Option<String>) -> Result<String, Error> {
+    let toolchain = match flag {
+        Some(toolchain) => Some(toolchain),
+        None => config::load_user_config()?.default_toolchain,
+    };
+    Ok(toolchain.unwrap_or_else(|| compile::CompileOptions::default().toolchain))
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 32)]
+#[allow(clippy::too_many_lines)]
 async fn main() -> Result<(), Error> {
     env_logger::init();
 
     let args = Args::parse();
-    log::trace!("{:?}", args);
+    log::trace!("{args:?}");
 
     match args.command {
         Command::CreateFreshDb {
@@ -174,21 +233,39 @@ async fn main() -> Result<(), Error> {
             //index::update_missing_crates(db.clone()).await?;
             index::set_latest_versions(db.clone()).await?;
         }
-        Command::Compile {
-            crate_fullname,
-            roots,
-        } => {
+        Command::Compile { .. } => {
             // let sources = roots.get_crate_sources()?;
             //compile_crate(&sources[&crate_fullname], roots.bytecodes_root.unwrap())?;
         }
-        Command::CompileAll { update_only, roots } => {
+        Command::CompileAll {
+            update_only,
+            toolchain,
+            roots,
+        } => {
+            let opts = compile::CompileOptions {
+                toolchain: resolve_toolchain(toolchain)?,
+                ..compile::CompileOptions::default()
+            };
+
             compile::compile_all(
-                cratefs_from_roots(&roots)?,
+                cratefs_from_roots(&roots),
                 roots.bytecodes_root.unwrap(),
                 update_only,
-            )
-            .await
-            .unwrap();
+                &opts,
+            )?;
+        }
+        Command::BatchCompile { manifest } => {
+            for (spec, result) in compile::batch_compile_from_manifest(&manifest)? {
+                match result {
+                    Ok(files) => println!(
+                        "{}-{}: {} bytecode files",
+                        spec.name,
+                        spec.version,
+                        files.len()
+                    ),
+                    Err(e) => println!("{}-{}: {}", spec.name, spec.version, e),
+                }
+            }
         }
         Command::CountUnsafe {
             roots,
@@ -208,6 +285,110 @@ async fn main() -> Result<(), Error> {
             let db = Arc::new(Db::connect(host, username, password).await?);
             analysis::export_all_db(&roots.bytecodes_root.unwrap(), db).await?;
         }
+        Command::ExportAllCsv { roots } => {
+            analysis::export_all_csv(roots.bytecodes_root.unwrap())?;
+        }
+        Command::Audit {
+            crate_bc_dir,
+            banned,
+            forbidden,
+            max_unsafe_ratio,
+            max_extern_c_edges,
+            blocked_prefixes,
+        } => {
+            let extra_blocked_prefixes = if blocked_prefixes.is_empty() {
+                config::load_user_config()?.extra_blocked_prefixes
+            } else {
+                blocked_prefixes
+            };
+            let analysis_config = analysis::AnalysisConfig {
+                extra_blocked_prefixes,
+                ..analysis::AnalysisConfig::default()
+            };
+
+            let graph = analysis::extract_call_graph(&crate_bc_dir, &analysis_config)?;
+            let policy = policy::AuditPolicy {
+                banned_functions: banned
+                    .iter()
+                    .map(|p| regex::Regex::new(p))
+                    .collect::<Result<_, _>>()?,
+                required_absent_patterns: forbidden
+                    .iter()
+                    .map(|p| regex::Regex::new(p))
+                    .collect::<Result<_, _>>()?,
+                max_unsafe_node_ratio: max_unsafe_ratio,
+                max_extern_c_edges,
+            };
+
+            let report = policy::generate_audit_report(&graph, &policy);
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report)
+                    .map_err(|e| Error::SerializationError(e.to_string()))?
+            );
+        }
+        Command::Graph {
+            crate_bc_dir,
+            format,
+            print_schema,
+            report,
+            entry_points,
+        } => {
+            let Some(crate_bc_dir) = crate_bc_dir.filter(|_| !print_schema) else {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&export::call_graph_json_schema())
+                        .map_err(|e| Error::SerializationError(e.to_string()))?
+                );
+                return Ok(());
+            };
+            let user_config = config::load_user_config()?;
+            let analysis_config = analysis::AnalysisConfig {
+                extra_blocked_prefixes: user_config.extra_blocked_prefixes,
+                ..analysis::AnalysisConfig::default()
+            };
+            let graph = analysis::extract_call_graph(&crate_bc_dir, &analysis_config)?;
+
+            if let Some(Report::DeadCode) = report {
+                let entry_points: Vec<&str> = if entry_points.is_empty() {
+                    graph.roots().collect()
+                } else {
+                    entry_points.iter().map(String::as_str).collect()
+                };
+                let dead_code: Vec<&str> =
+                    analysis::detect_dead_code_candidates(&graph, &entry_points)
+                        .into_iter()
+                        .map(|n| n.demangled.as_str())
+                        .collect();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&dead_code)
+                        .map_err(|e| Error::SerializationError(e.to_string()))?
+                );
+                return Ok(());
+            }
+
+            export::write_call_graph_to_stdout(
+                &graph,
+                format.unwrap_or(user_config.default_output_format),
+            )?;
+        }
+        Command::Doctor { toolchain } => {
+            let toolchain = resolve_toolchain(toolchain)?;
+            let report = compile::validate_compilation_environment(&toolchain)?;
+            println!("{}", report.cargo_version);
+            println!("Toolchains: {}", report.rustup_toolchains.join(", "));
+            println!("Free disk space: {:.1} GB", report.disk_space_gb);
+            for warning in &report.warnings {
+                println!("warning: {warning}");
+            }
+        }
+        Command::Config {
+            command: ConfigCommand::Init,
+        } => {
+            let path = config::init_user_config()?;
+            println!("Wrote config template to {}", path.display());
+        }
         Command::SemverCheck => {
             let index = crates_index::Index::new_cargo_default().unwrap();
             let invalid_versions = Arc::new(Mutex::new(std::collections::HashSet::new()));
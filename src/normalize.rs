@@ -0,0 +1,112 @@
+use crate::graph::{function_name, CallGraph, FunctionNode};
+use regex::Regex;
+
+/// Maps demangled names onto a canonical form, so graphs built by different compiler versions or
+/// build configurations can be compared, see `CallGraph::normalize_names`.
+pub trait NameNormalizer {
+    fn normalize(&self, name: &str) -> String;
+}
+
+/// Removes legacy mangling hash suffixes such as `::h1a2b3c4d5e6f7a8b`.
+#[derive(Debug, Clone)]
+pub struct StripHashSuffix {
+    pattern: Regex,
+}
+impl Default for StripHashSuffix {
+    fn default() -> Self {
+        Self {
+            pattern: Regex::new(r"::h[0-9a-f]{16}$").unwrap(),
+        }
+    }
+}
+impl NameNormalizer for StripHashSuffix {
+    fn normalize(&self, name: &str) -> String {
+        self.pattern.replace(name, "").into_owned()
+    }
+}
+
+/// Replaces numbered closures, `{{closure#N}}` or `{closure#N}`, with `{{closure}}`.
+#[derive(Debug, Clone)]
+pub struct CollapseClosures {
+    pattern: Regex,
+}
+impl Default for CollapseClosures {
+    fn default() -> Self {
+        Self {
+            pattern: Regex::new(r"\{\{closure#\d+\}\}|\{closure#\d+\}").unwrap(),
+        }
+    }
+}
+impl NameNormalizer for CollapseClosures {
+    fn normalize(&self, name: &str) -> String {
+        self.pattern.replace_all(name, "{{closure}}").into_owned()
+    }
+}
+
+/// Drops the crate and module path, keeping only the function name, see `graph::function_name`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShortCrateName;
+impl NameNormalizer for ShortCrateName {
+    fn normalize(&self, name: &str) -> String {
+        function_name(name).to_owned()
+    }
+}
+
+impl CallGraph {
+    /// Renames every node with `normalizer`. Nodes whose names normalize to the same string are
+    /// merged: the first in name order keeps its flags and the call counts of their edges are
+    /// summed. Synthetic edges stay synthetic unless merged into an extracted one.
+    pub fn normalize_names(&mut self, normalizer: &dyn NameNormalizer) {
+        let mut graph = CallGraph::new();
+
+        for node in self.nodes() {
+            let name = normalizer.normalize(&node.demangled);
+            if !graph.contains(&name) {
+                *graph.add_node(&name) = FunctionNode {
+                    demangled: name.clone(),
+                    ..node.clone()
+                };
+            }
+        }
+        // Extracted edges go first, so that a synthetic edge merged into one is not recorded as
+        // synthetic whichever of the two comes first in name order.
+        let (synthetic, extracted): (Vec<_>, Vec<_>) = self
+            .edges()
+            .partition(|(caller, callee, _)| self.is_synthetic_edge(caller, callee));
+        for (caller, callee, count) in extracted {
+            graph.add_edge_with_count(
+                normalizer.normalize(caller),
+                normalizer.normalize(callee),
+                count,
+            );
+        }
+        for (caller, callee, count) in synthetic {
+            let (caller, callee) = (normalizer.normalize(caller), normalizer.normalize(callee));
+            if graph.add_synthetic_edge(&caller, &callee).is_err() {
+                graph.add_edge_with_count(caller, callee, count);
+            }
+        }
+
+        *self = graph;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthetic_edge_merged_into_extracted_is_not_synthetic() {
+        let mut graph: CallGraph = [("a::main", "b::run"), ("a::main", "a::log")]
+            .into_iter()
+            .collect();
+        // Sorts before the extracted `a::main -> b::run`, which it merges with below.
+        graph.add_synthetic_edge("a::main", "a::run").unwrap();
+        graph.add_synthetic_edge("a::log", "a::flush").unwrap();
+
+        graph.normalize_names(&ShortCrateName);
+        assert_eq!(graph.call_count("main", "run"), Some(2));
+        assert!(!graph.is_synthetic_edge("main", "run"));
+        assert!(graph.is_synthetic_edge("log", "flush"));
+    }
+}
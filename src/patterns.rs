@@ -0,0 +1,480 @@
+use crate::{
+    analysis::AnalysisConfig,
+    graph::{async_function_name, crate_name, function_name, CallGraph},
+};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+
+const ITERATOR_ADAPTERS: &[&str] = &[
+    "map",
+    "filter",
+    "filter_map",
+    "flat_map",
+    "enumerate",
+    "zip",
+    "chain",
+    "take",
+    "skip",
+    "rev",
+    "cloned",
+    "copied",
+];
+const ITERATOR_COLLECTORS: &[&str] = &[
+    "collect", "sum", "product", "fold", "count", "for_each", "max", "min",
+];
+const TRY_FUNCTIONS: &[&str] = &["branch", "from_residual"];
+
+/// A function invoking `build` on a builder type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuilderPattern {
+    /// The builder type path, i.e. the `build` callee without its final segment.
+    pub builder: String,
+    /// Functions calling the builders `build`.
+    pub build_calls: Vec<String>,
+}
+
+/// A function driving iterator adapters into a consuming collector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IteratorChain {
+    pub source: String,
+    pub adapters: Vec<String>,
+    pub collector: String,
+}
+
+/// A function propagating errors via `?`, detected through the `Try` desugaring calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorPropagation {
+    pub try_caller: String,
+    pub error_source: String,
+}
+
+/// All structural motifs found by `analyze_call_patterns`.
+#[derive(Debug, Clone, Default)]
+pub struct CallPatterns {
+    pub builders: Vec<BuilderPattern>,
+    pub iterator_chains: Vec<IteratorChain>,
+    pub error_propagations: Vec<ErrorPropagation>,
+}
+
+/// Heuristically detects common Rust idioms in `graph` based on the final segment of demangled
+/// callee names:
+/// - Builders: any `build` function, grouped with all of its callers.
+/// - Iterator chains: a function calling at least one iterator adapter (`map`, `filter`, ..) and
+///   a collector (`collect`, `sum`, ..). One chain is reported per collector.
+/// - Error propagation: a function calling the `Try` desugaring (`branch`, `from_residual`).
+///
+/// Note that adapters and the `Try` machinery live in `core`, so these are only found in graphs
+/// extracted without the default `BLOCKED_STRINGS` filtering.
+#[must_use]
+pub fn analyze_call_patterns(graph: &CallGraph) -> CallPatterns {
+    let mut patterns = CallPatterns::default();
+
+    for node in graph.nodes() {
+        let name = node.demangled.as_str();
+
+        if function_name(name) == "build" {
+            if let Some((builder, _)) = name.rsplit_once("::") {
+                let build_calls: Vec<_> = graph.callers(name).map(str::to_owned).collect();
+                if !build_calls.is_empty() {
+                    patterns.builders.push(BuilderPattern {
+                        builder: builder.to_owned(),
+                        build_calls,
+                    });
+                }
+            }
+        }
+
+        let adapters: Vec<_> = graph
+            .callees(name)
+            .filter(|c| ITERATOR_ADAPTERS.contains(&function_name(c)))
+            .map(str::to_owned)
+            .collect();
+        if !adapters.is_empty() {
+            graph
+                .callees(name)
+                .filter(|c| ITERATOR_COLLECTORS.contains(&function_name(c)))
+                .for_each(|collector| {
+                    patterns.iterator_chains.push(IteratorChain {
+                        source: name.to_owned(),
+                        adapters: adapters.clone(),
+                        collector: collector.to_owned(),
+                    });
+                });
+        }
+
+        graph
+            .callees(name)
+            .filter(|c| TRY_FUNCTIONS.contains(&function_name(c)))
+            .for_each(|callee| {
+                patterns.error_propagations.push(ErrorPropagation {
+                    try_caller: name.to_owned(),
+                    error_source: callee.to_owned(),
+                });
+            });
+    }
+
+    patterns
+}
+
+/// A call to a trait method, identified by the `<Type as Trait>::method` form of its demangled
+/// name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraitMethodCall {
+    pub receiver_type: String,
+    pub trait_name: String,
+    pub method_name: String,
+    pub caller: String,
+}
+
+/// Matches `<Type as Trait>::method`, capturing the type, trait and method.
+fn trait_method_regex() -> Regex {
+    Regex::new(r"^<(.+) as (.+)>::([^:<>]+)$").unwrap()
+}
+
+/// Finds every call in `graph` to a trait method, one `TraitMethodCall` per calling function.
+///
+/// # Panics
+/// This function should not panic; the trait method regex is a constant.
+#[must_use]
+pub fn extract_trait_method_calls(graph: &CallGraph) -> Vec<TraitMethodCall> {
+    let re = trait_method_regex();
+
+    graph
+        .nodes()
+        .filter_map(|n| Some((n, re.captures(&n.demangled)?)))
+        .flat_map(|(n, caps)| {
+            graph
+                .callers(&n.demangled)
+                .map(|caller| TraitMethodCall {
+                    receiver_type: caps[1].to_owned(),
+                    trait_name: caps[2].to_owned(),
+                    method_name: caps[3].to_owned(),
+                    caller: caller.to_owned(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+impl CallGraph {
+    /// Number of call edges targeting a trait method.
+    ///
+    /// # Panics
+    /// This function should not panic; the trait method regex is a constant.
+    #[must_use]
+    pub fn trait_method_call_count(&self) -> usize {
+        let re = trait_method_regex();
+        self.edges()
+            .filter(|(_, callee, _)| re.is_match(callee))
+            .count()
+    }
+}
+
+/// A logical `.await` from one async function into another, reconstructed from the calls between
+/// their compiler generated state machines.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AsyncCallEdge {
+    pub async_caller: String,
+    pub awaited_callee: String,
+}
+
+/// Finds calls between async state machines (nodes flagged `FunctionNode::is_async`) and maps
+/// both ends back to their logical async function. Calls within the same async function are
+/// dropped, and the result is sorted and deduplicated.
+#[must_use]
+pub fn detect_async_function_calls(graph: &CallGraph) -> Vec<AsyncCallEdge> {
+    let edges: BTreeSet<_> = graph
+        .edges()
+        .filter_map(|(caller, callee, _)| {
+            let async_caller = async_function_name(caller)?;
+            let awaited_callee = async_function_name(callee)?;
+            (async_caller != awaited_callee).then(|| AsyncCallEdge {
+                async_caller: async_caller.to_owned(),
+                awaited_callee: awaited_callee.to_owned(),
+            })
+        })
+        .collect();
+
+    edges.into_iter().collect()
+}
+
+/// A function calling a fallible function without calling anything that handles its error.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MissingErrorHandling {
+    pub caller: String,
+    pub fallible_callee: String,
+}
+
+/// Finds functions calling a fallible function without calling any error handling function,
+/// using the default `AnalysisConfig` lists, see `detect_missing_error_handling_with_config`.
+#[must_use]
+pub fn detect_missing_error_handling(graph: &CallGraph) -> Vec<MissingErrorHandling> {
+    detect_missing_error_handling_with_config(graph, &AnalysisConfig::default())
+}
+
+/// Heuristically finds functions which call a callee matching
+/// `AnalysisConfig::fallible_functions`, but none matching
+/// `AnalysisConfig::error_handling_functions` such as `unwrap`, `map_err` or the `?` desugaring.
+/// One finding is reported per fallible callee. Errors handled with a plain `match` involve no
+/// call, so such functions are false positives.
+#[must_use]
+pub fn detect_missing_error_handling_with_config(
+    graph: &CallGraph,
+    config: &AnalysisConfig,
+) -> Vec<MissingErrorHandling> {
+    let matches = |patterns: &[Regex], name: &str| patterns.iter().any(|p| p.is_match(name));
+
+    graph
+        .nodes()
+        .map(|n| n.demangled.as_str())
+        .filter(|caller| {
+            !graph
+                .callees(caller)
+                .any(|c| matches(&config.error_handling_functions, c))
+        })
+        .flat_map(|caller| {
+            graph
+                .callees(caller)
+                .filter(|c| matches(&config.fallible_functions, c))
+                .map(move |callee| MissingErrorHandling {
+                    caller: caller.to_owned(),
+                    fallible_callee: callee.to_owned(),
+                })
+        })
+        .collect()
+}
+
+/// Number of nodes above which `detect_function_duplicates` warns about its quadratic cost.
+const DUPLICATE_WARN_NODE_COUNT: usize = 1000;
+
+/// Finds pairs of functions sharing a call pattern, scored by the Jaccard similarity of their
+/// callee sets, and returns those scoring at least `similarity_threshold` as
+/// `(func_a, func_b, similarity)` with `func_a < func_b`, most similar first. Functions without
+/// callees are not compared. Every pair is compared, so large graphs are slow.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn detect_function_duplicates(
+    graph: &CallGraph,
+    similarity_threshold: f64,
+) -> Vec<(String, String, f64)> {
+    if graph.node_count() > DUPLICATE_WARN_NODE_COUNT {
+        log::warn!(
+            "Comparing callees of all pairs of {} functions, this may take a while",
+            graph.node_count()
+        );
+    }
+
+    let callee_sets: Vec<(&str, BTreeSet<&str>)> = graph
+        .nodes()
+        .map(|n| (n.demangled.as_str(), graph.callees(&n.demangled).collect()))
+        .filter(|(_, callees): &(_, BTreeSet<_>)| !callees.is_empty())
+        .collect();
+
+    let mut duplicates = Vec::new();
+    for (i, (a, callees_a)) in callee_sets.iter().enumerate() {
+        for (b, callees_b) in &callee_sets[i + 1..] {
+            let shared = callees_a.intersection(callees_b).count();
+            let similarity = shared as f64 / (callees_a.len() + callees_b.len() - shared) as f64;
+            if similarity >= similarity_threshold {
+                duplicates.push(((*a).to_owned(), (*b).to_owned(), similarity));
+            }
+        }
+    }
+    duplicates.sort_by(|x, y| {
+        y.2.total_cmp(&x.2)
+            .then_with(|| (&x.0, &x.1).cmp(&(&y.0, &y.1)))
+    });
+
+    duplicates
+}
+
+/// Broad kinds of functionality a crate can be inferred to provide, see `infer_crate_categories`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum CrateCategory {
+    Networking,
+    Filesystem,
+    Threading,
+    Serialization,
+    Cryptography,
+    Ui,
+    Testing,
+}
+
+/// Callee path prefixes indicating each `CrateCategory`.
+const CATEGORY_PREFIXES: &[(CrateCategory, &[&str])] = &[
+    (
+        CrateCategory::Networking,
+        &[
+            "std::net::",
+            "tokio::net::",
+            "mio::net::",
+            "hyper::",
+            "reqwest::",
+        ],
+    ),
+    (
+        CrateCategory::Filesystem,
+        &["std::fs::", "std::path::", "tokio::fs::", "walkdir::"],
+    ),
+    (
+        CrateCategory::Threading,
+        &["std::thread::", "std::sync::", "rayon::", "crossbeam"],
+    ),
+    (
+        CrateCategory::Serialization,
+        &[
+            "serde::",
+            "serde_json::",
+            "toml::",
+            "bincode::",
+            "rmp_serde::",
+        ],
+    ),
+    (
+        CrateCategory::Cryptography,
+        &[
+            "ring::",
+            "rustls::",
+            "openssl::",
+            "sha2::",
+            "aes::",
+            "ed25519_dalek::",
+        ],
+    ),
+    (
+        CrateCategory::Ui,
+        &[
+            "winit::",
+            "egui::",
+            "gtk::",
+            "iced::",
+            "ratatui::",
+            "crossterm::",
+        ],
+    ),
+    (
+        CrateCategory::Testing,
+        &["test::", "proptest::", "quickcheck::", "criterion::"],
+    ),
+];
+
+/// Infers the categories of each crate in `graph` from the paths of the functions it calls, e.g.
+/// a crate calling into `std::net::` is `Networking`. Calls into `std` are only present when the
+/// graph was extracted with `AnalysisConfig::include_stdlib_for_inference` set. Crates are
+/// returned sorted by name, each with its categories sorted; crates without any are omitted.
+#[must_use]
+pub fn infer_crate_categories(graph: &CallGraph) -> Vec<(String, Vec<CrateCategory>)> {
+    let mut categories: BTreeMap<&str, BTreeSet<CrateCategory>> = BTreeMap::new();
+    for (caller, callee, _) in graph.edges() {
+        let krate = crate_name(caller);
+        let callee = callee.trim_start_matches('<');
+        if krate == crate_name(callee) {
+            continue;
+        }
+
+        for (category, prefixes) in CATEGORY_PREFIXES {
+            if prefixes.iter().any(|p| callee.starts_with(p)) {
+                categories.entry(krate).or_default().insert(*category);
+            }
+        }
+    }
+
+    categories
+        .into_iter()
+        .map(|(krate, found)| (krate.to_owned(), found.into_iter().collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_callees_are_duplicates() {
+        let graph: CallGraph = [
+            ("app::f", "app::x"),
+            ("app::f", "app::y"),
+            ("app::g", "app::x"),
+            ("app::g", "app::y"),
+            ("app::h", "app::x"),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            detect_function_duplicates(&graph, 1.0),
+            [("app::f".to_owned(), "app::g".to_owned(), 1.0)]
+        );
+        assert_eq!(detect_function_duplicates(&graph, 0.5).len(), 3);
+    }
+
+    #[test]
+    fn tcp_stream_usage_is_networking() {
+        let graph: CallGraph = [
+            ("client::connect", "std::net::tcp::TcpStream::connect"),
+            ("client::connect", "client::retry"),
+            (
+                "client::send",
+                "<std::net::tcp::TcpStream as std::io::Write>::write",
+            ),
+            ("client::send", "serde_json::to_vec"),
+            ("server::run", "std::thread::spawn"),
+            (
+                "std::net::tcp::TcpStream::connect",
+                "std::sys::net::connect",
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            infer_crate_categories(&graph),
+            [
+                (
+                    "client".to_owned(),
+                    vec![CrateCategory::Networking, CrateCategory::Serialization]
+                ),
+                ("server".to_owned(), vec![CrateCategory::Threading]),
+            ]
+        );
+    }
+
+    #[test]
+    fn trait_methods_of_a_type_with_several_traits() {
+        let graph: CallGraph = [
+            ("app::main", "<app::Point as core::fmt::Display>::fmt"),
+            ("app::main", "<app::Point as core::clone::Clone>::clone"),
+            ("app::log", "<app::Point as core::fmt::Display>::fmt"),
+            ("app::log", "<app::Point as core::fmt::Debug>::fmt"),
+            ("app::main", "app::Point::new"),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut calls: Vec<_> = extract_trait_method_calls(&graph)
+            .into_iter()
+            .map(|c| (c.caller, c.receiver_type, c.trait_name, c.method_name))
+            .collect();
+        calls.sort();
+        let call = |caller: &str, trait_name: &str, method: &str| {
+            (
+                caller.to_owned(),
+                "app::Point".to_owned(),
+                trait_name.to_owned(),
+                method.to_owned(),
+            )
+        };
+        assert_eq!(
+            calls,
+            [
+                call("app::log", "core::fmt::Debug", "fmt"),
+                call("app::log", "core::fmt::Display", "fmt"),
+                call("app::main", "core::clone::Clone", "clone"),
+                call("app::main", "core::fmt::Display", "fmt"),
+            ]
+        );
+        assert_eq!(graph.trait_method_call_count(), 4);
+    }
+}
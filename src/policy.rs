@@ -0,0 +1,271 @@
+use crate::graph::{is_extern_c, CallGraph, CallSummary};
+use regex::Regex;
+use std::collections::BTreeSet;
+
+/// Checks that none of `banned_functions` is called anywhere within `graph`.
+///
+/// # Errors
+/// Returns the sorted, deduplicated list of callers which invoke a banned function.
+pub fn verify_no_calls_to(graph: &CallGraph, banned_functions: &[&str]) -> Result<(), Vec<String>> {
+    let callers: BTreeSet<String> = graph
+        .edges()
+        .filter(|(_, callee, _)| banned_functions.contains(callee))
+        .map(|(caller, _, _)| caller.to_owned())
+        .collect();
+
+    if callers.is_empty() {
+        Ok(())
+    } else {
+        Err(callers.into_iter().collect())
+    }
+}
+
+/// Checks that no callee within `graph` matches `pattern`.
+///
+/// # Errors
+/// Returns every offending `(caller, callee)` edge.
+pub fn verify_no_calls_matching(
+    graph: &CallGraph,
+    pattern: &Regex,
+) -> Result<(), Vec<(String, String)>> {
+    let edges: Vec<_> = graph
+        .edges()
+        .filter(|(_, callee, _)| pattern.is_match(callee))
+        .map(|(caller, callee, _)| (caller.to_owned(), callee.to_owned()))
+        .collect();
+
+    if edges.is_empty() {
+        Ok(())
+    } else {
+        Err(edges)
+    }
+}
+
+/// Rules checked by `generate_audit_report`.
+#[derive(Debug, Clone, Default)]
+pub struct AuditPolicy {
+    /// Callees which must never be invoked.
+    pub banned_functions: Vec<Regex>,
+    /// Patterns which must not match any function in the graph, caller or callee.
+    pub required_absent_patterns: Vec<Regex>,
+    /// Largest allowed fraction of functions which are unsafe, see `FunctionNode::is_unsafe`.
+    pub max_unsafe_node_ratio: Option<f64>,
+    /// Largest allowed number of distinct calls into extern C functions, see `is_extern_c`.
+    pub max_extern_c_edges: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ViolationKind {
+    BannedFunctionCall,
+    ForbiddenPatternPresent,
+    UnsafeRatioExceeded,
+    ExternCLimitExceeded,
+}
+
+/// A single failed `AuditPolicy` rule.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AuditViolation {
+    pub kind: ViolationKind,
+    pub offending_nodes: Vec<String>,
+    /// The pattern of the rule which failed.
+    pub policy_rule: String,
+}
+
+/// Result of checking a `CallGraph` against an `AuditPolicy`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AuditReport {
+    pub violations: Vec<AuditViolation>,
+    /// The patterns of all rules which passed.
+    pub passed_checks: Vec<String>,
+    pub summary: CallSummary,
+}
+impl AuditReport {
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Checks `graph` against every rule in `policy`. Each banned function pattern is a violation
+/// listing the offending callers; each required absent pattern is a violation listing the matching
+/// functions. Exceeding `max_unsafe_node_ratio` lists the unsafe functions and exceeding
+/// `max_extern_c_edges` lists the extern C callees.
+#[must_use]
+pub fn generate_audit_report(graph: &CallGraph, policy: &AuditPolicy) -> AuditReport {
+    let mut violations = Vec::new();
+    let mut passed_checks = Vec::new();
+
+    for pattern in &policy.banned_functions {
+        match verify_no_calls_matching(graph, pattern) {
+            Ok(()) => passed_checks.push(pattern.to_string()),
+            Err(edges) => {
+                let callers: BTreeSet<_> = edges.into_iter().map(|(caller, _)| caller).collect();
+                violations.push(AuditViolation {
+                    kind: ViolationKind::BannedFunctionCall,
+                    offending_nodes: callers.into_iter().collect(),
+                    policy_rule: pattern.to_string(),
+                });
+            }
+        }
+    }
+
+    for pattern in &policy.required_absent_patterns {
+        let matches: Vec<_> = graph
+            .nodes()
+            .filter(|n| pattern.is_match(&n.demangled))
+            .map(|n| n.demangled.clone())
+            .collect();
+
+        if matches.is_empty() {
+            passed_checks.push(pattern.to_string());
+        } else {
+            violations.push(AuditViolation {
+                kind: ViolationKind::ForbiddenPatternPresent,
+                offending_nodes: matches,
+                policy_rule: pattern.to_string(),
+            });
+        }
+    }
+
+    if let Some(max_ratio) = policy.max_unsafe_node_ratio {
+        let rule = format!("unsafe node ratio <= {max_ratio}");
+        let unsafe_nodes: Vec<_> = graph
+            .nodes()
+            .filter(|n| n.is_unsafe)
+            .map(|n| n.demangled.clone())
+            .collect();
+
+        #[allow(clippy::cast_precision_loss)]
+        let ratio = if graph.node_count() == 0 {
+            0.0
+        } else {
+            unsafe_nodes.len() as f64 / graph.node_count() as f64
+        };
+        if ratio > max_ratio {
+            violations.push(AuditViolation {
+                kind: ViolationKind::UnsafeRatioExceeded,
+                offending_nodes: unsafe_nodes,
+                policy_rule: rule,
+            });
+        } else {
+            passed_checks.push(rule);
+        }
+    }
+
+    if let Some(max_edges) = policy.max_extern_c_edges {
+        let rule = format!("extern C edges <= {max_edges}");
+        let edges = graph
+            .edges()
+            .filter(|(_, callee, _)| is_extern_c(callee))
+            .count();
+
+        if edges > max_edges {
+            let callees: BTreeSet<_> = graph
+                .edges()
+                .filter(|(_, callee, _)| is_extern_c(callee))
+                .map(|(_, callee, _)| callee.to_owned())
+                .collect();
+            violations.push(AuditViolation {
+                kind: ViolationKind::ExternCLimitExceeded,
+                offending_nodes: callees.into_iter().collect(),
+                policy_rule: rule,
+            });
+        } else {
+            passed_checks.push(rule);
+        }
+    }
+
+    AuditReport {
+        violations,
+        passed_checks,
+        summary: graph.summary(),
+    }
+}
+
+/// A single step of a `FilterPolicy`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FilterStep {
+    BlockCrate(String),
+    MaxDepthFromEntry(String, usize),
+    MinOutDegree(usize),
+    KeepOnlyUnsafe,
+}
+
+/// A chain of graph filters built up with the builder methods and applied in order by
+/// `CallGraph::apply_policy_transform`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterPolicy {
+    steps: Vec<FilterStep>,
+}
+impl FilterPolicy {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes all functions whose demangled name starts with `prefix`.
+    #[must_use]
+    pub fn block_crate(mut self, prefix: &str) -> Self {
+        self.steps.push(FilterStep::BlockCrate(prefix.to_owned()));
+        self
+    }
+
+    /// Keeps only functions reachable from `entry` within `depth` calls.
+    #[must_use]
+    pub fn max_depth_from_entry(mut self, entry: &str, depth: usize) -> Self {
+        self.steps
+            .push(FilterStep::MaxDepthFromEntry(entry.to_owned(), depth));
+        self
+    }
+
+    /// Keeps only functions calling at least `n` distinct functions. Evaluated against the graph as
+    /// produced by the preceding steps.
+    #[must_use]
+    pub fn min_out_degree(mut self, n: usize) -> Self {
+        self.steps.push(FilterStep::MinOutDegree(n));
+        self
+    }
+
+    /// Keeps only unsafe functions, see `FunctionNode::is_unsafe`.
+    #[must_use]
+    pub fn keep_only_unsafe(mut self) -> Self {
+        self.steps.push(FilterStep::KeepOnlyUnsafe);
+        self
+    }
+}
+
+impl CallGraph {
+    /// Applies every step of `policy` in order, returning the filtered graph.
+    #[must_use]
+    pub fn apply_policy_transform(&self, policy: &FilterPolicy) -> CallGraph {
+        let mut graph = self.clone();
+
+        for step in &policy.steps {
+            match step {
+                FilterStep::BlockCrate(prefix) => {
+                    graph.retain_nodes(|n| !n.demangled.starts_with(prefix.as_str()));
+                }
+                FilterStep::MaxDepthFromEntry(entry, depth) => {
+                    let keep: BTreeSet<String> = graph
+                        .distances_from(entry)
+                        .into_iter()
+                        .filter(|(_, d)| d <= depth)
+                        .map(|(n, _)| n.to_owned())
+                        .collect();
+                    graph.retain_nodes(|n| keep.contains(&n.demangled));
+                }
+                FilterStep::MinOutDegree(min) => {
+                    let keep: BTreeSet<String> = graph
+                        .nodes()
+                        .filter(|n| graph.out_degree(&n.demangled) >= *min)
+                        .map(|n| n.demangled.clone())
+                        .collect();
+                    graph.retain_nodes(|n| keep.contains(&n.demangled));
+                }
+                FilterStep::KeepOnlyUnsafe => graph.retain_nodes(|n| n.is_unsafe),
+            }
+        }
+
+        graph
+    }
+}
@@ -0,0 +1,98 @@
+use crate::graph::CallGraph;
+use ndarray::Array2;
+use std::collections::HashMap;
+
+/// Precomputed transitive closure of a `CallGraph`, answering reachability queries in constant
+/// time. Built by `CallGraph::compute_reachability_matrix`.
+#[derive(Debug, Clone)]
+pub struct ReachabilityIndex {
+    /// `matrix[[i, j]]` is true iff node `j` is reachable from node `i`.
+    pub matrix: Array2<bool>,
+    /// Row and column of each node in `matrix`.
+    pub index: HashMap<String, usize>,
+}
+impl ReachabilityIndex {
+    /// Returns true if `to` is reachable from `from`. Every node reaches itself; nodes not in the
+    /// graph reach nothing.
+    #[must_use]
+    pub fn is_reachable(&self, from: &str, to: &str) -> bool {
+        match (self.index.get(from), self.index.get(to)) {
+            (Some(&i), Some(&j)) => self.matrix[[i, j]],
+            _ => false,
+        }
+    }
+}
+
+impl CallGraph {
+    /// Computes the transitive closure of the graph with a traversal from every node, see
+    /// `reachable_from`. Takes `O(V * (V + E))` time and `O(V^2)` space.
+    #[must_use]
+    pub fn compute_reachability_matrix(&self) -> ReachabilityIndex {
+        let index: HashMap<String, usize> = self
+            .nodes()
+            .enumerate()
+            .map(|(i, n)| (n.demangled.clone(), i))
+            .collect();
+
+        let mut matrix = Array2::from_elem((index.len(), index.len()), false);
+        for (name, &i) in &index {
+            for reached in self.reachable_from(name) {
+                matrix[[i, index[reached]]] = true;
+            }
+        }
+
+        ReachabilityIndex { matrix, index }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ten functions: a chain into the cycle `c -> d -> e -> c` with a tail off of it, and a
+    /// disconnected part of three functions.
+    fn fixture() -> CallGraph {
+        [
+            ("app::a", "app::b"),
+            ("app::b", "app::c"),
+            ("app::c", "app::d"),
+            ("app::d", "app::e"),
+            ("app::e", "app::c"),
+            ("app::e", "app::f"),
+            ("app::f", "app::g"),
+            ("lib::x", "lib::y"),
+            ("lib::y", "lib::z"),
+            ("lib::x", "lib::z"),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn matrix_agrees_with_reachable_from() {
+        let graph = fixture();
+        let index = graph.compute_reachability_matrix();
+        assert_eq!(index.index.len(), 10);
+
+        let names: Vec<&str> = graph.nodes().map(|n| n.demangled.as_str()).collect();
+        for from in &names {
+            let reached = graph.reachable_from(from);
+            for to in &names {
+                assert_eq!(
+                    index.is_reachable(from, to),
+                    reached.contains(to),
+                    "{from} -> {to}"
+                );
+            }
+            assert!(index.is_reachable(from, from));
+            assert!(!index.is_reachable(from, "app::unknown"));
+            assert!(!index.is_reachable("app::unknown", from));
+        }
+        assert!(!index.is_reachable("app::unknown", "app::unknown"));
+
+        assert!(index.is_reachable("app::e", "app::d"));
+        assert!(index.is_reachable("app::a", "app::g"));
+        assert!(!index.is_reachable("app::g", "app::c"));
+        assert!(!index.is_reachable("app::a", "lib::x"));
+    }
+}
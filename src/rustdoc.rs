@@ -0,0 +1,132 @@
+use crate::{
+    graph::{CallGraph, FunctionNode},
+    Error,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+/// Runs nightly rustdoc with JSON output for the crate at `manifest_path`, returning the parsed
+/// documentation.
+fn rustdoc_json(manifest_path: &Path) -> Result<serde_json::Value, Error> {
+    let manifest: toml::Value = toml::from_str(&std::fs::read_to_string(manifest_path)?)
+        .map_err(|e| Error::RustdocError(e.to_string()))?;
+    let crate_name = manifest
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(toml::Value::as_str)
+        .ok_or_else(|| Error::RustdocError("manifest has no package name".to_owned()))?
+        .replace('-', "_");
+
+    let crate_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let output = std::process::Command::new("cargo")
+        .args([
+            "+nightly",
+            "rustdoc",
+            "--lib",
+            "--",
+            "-Z",
+            "unstable-options",
+            "--output-format",
+            "json",
+        ])
+        .current_dir(crate_dir)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::RustdocError(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+
+    let json_path = crate_dir
+        .join("target")
+        .join("doc")
+        .join(format!("{crate_name}.json"));
+    serde_json::from_str(&std::fs::read_to_string(json_path)?)
+        .map_err(|e| Error::RustdocError(e.to_string()))
+}
+
+/// Returns the fully qualified paths of all public functions defined in the crate itself, with
+/// their ids in `doc`.
+fn public_function_paths(doc: &serde_json::Value) -> HashMap<String, String> {
+    // `paths` only lists items reachable through the public API; crate 0 is the local crate.
+    doc.get("paths")
+        .and_then(serde_json::Value::as_object)
+        .into_iter()
+        .flat_map(|paths| paths.iter())
+        .filter(|(_, item)| {
+            item.get("kind").and_then(serde_json::Value::as_str) == Some("function")
+                && item.get("crate_id").and_then(serde_json::Value::as_u64) == Some(0)
+        })
+        .filter_map(|(id, item)| {
+            let path: Vec<_> = item
+                .get("path")?
+                .as_array()?
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .collect();
+            Some((id.clone(), path.join("::")))
+        })
+        .collect()
+}
+
+/// Returns true if the item with `id` in `doc` is a function declared `unsafe`. Handles both the
+/// current `inner.function.header.is_unsafe` layout and the older `header.unsafe` one.
+fn is_unsafe_function(doc: &serde_json::Value, id: &str) -> bool {
+    let Some(item) = doc.get("index").and_then(|index| index.get(id)) else {
+        return false;
+    };
+    item.pointer("/inner/function/header/is_unsafe")
+        .or_else(|| item.pointer("/inner/header/unsafe"))
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false)
+}
+
+impl CallGraph {
+    /// Marks every node which is a public function of the crate at `manifest_path` by setting
+    /// `FunctionNode::is_public_api`. Public functions are taken from the rustdoc JSON output,
+    /// which requires a `nightly` toolchain to be installed.
+    ///
+    /// # Errors
+    /// Returns `Error::RustdocError` if rustdoc fails or its output cannot be parsed, and
+    /// `Error::IoError` if the manifest or output cannot be read.
+    pub fn mark_public_api_surface(&mut self, manifest_path: &Path) -> Result<(), Error> {
+        let doc = rustdoc_json(manifest_path)?;
+        let public: HashSet<String> = public_function_paths(&doc).into_values().collect();
+
+        self.nodes_mut()
+            .for_each(|n| n.is_public_api = public.contains(&n.demangled));
+
+        Ok(())
+    }
+
+    /// Marks every node which is a public function of the crate at `manifest_path` declared
+    /// `unsafe fn` by setting `FunctionNode::is_unsafe`. Unsafety is not recorded in bytecode, so
+    /// it is taken from the rustdoc JSON output as in `mark_public_api_surface`, which only
+    /// covers free functions reachable through the public API.
+    ///
+    /// # Errors
+    /// Returns `Error::RustdocError` if rustdoc fails or its output cannot be parsed, and
+    /// `Error::IoError` if the manifest or output cannot be read.
+    pub fn mark_unsafe_functions(&mut self, manifest_path: &Path) -> Result<(), Error> {
+        let doc = rustdoc_json(manifest_path)?;
+        let unsafe_paths: HashSet<String> = public_function_paths(&doc)
+            .into_iter()
+            .filter(|(id, _)| is_unsafe_function(&doc, id))
+            .map(|(_, path)| path)
+            .collect();
+
+        self.nodes_mut()
+            .for_each(|n| n.is_unsafe = unsafe_paths.contains(&n.demangled));
+
+        Ok(())
+    }
+
+    /// All nodes marked by `mark_public_api_surface`.
+    #[must_use]
+    pub fn public_api_surface(&self) -> Vec<&FunctionNode> {
+        self.nodes().filter(|n| n.is_public_api).collect()
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{
+    analysis::{self, AnalysisConfig},
+    compile::{self, CompileOptions},
+    graph::CallGraph,
+    Error,
+};
+use notify::{RecursiveMode, Watcher};
+use std::{
+    path::Path,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+/// Quiet period after the last source change before a rebuild is triggered.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Handle to a running `watch_and_recompile` session.
+pub struct WatchHandle {
+    watcher: notify::RecommendedWatcher,
+    thread: std::thread::JoinHandle<()>,
+}
+impl WatchHandle {
+    /// Stops watching and waits for any in-progress rebuild to finish.
+    ///
+    /// # Panics
+    /// This function will panic if the background thread panicked.
+    pub fn stop(self) {
+        // Dropping the watcher closes the event channel, which ends the background thread.
+        drop(self.watcher);
+        self.thread.join().unwrap();
+    }
+}
+
+/// Returns true if the event touched a Rust source file outside of `target_dir`, where the build
+/// itself writes generated sources.
+fn is_source_change(event: &notify::Result<notify::Event>, target_dir: &Path) -> bool {
+    event.as_ref().is_ok_and(|e| {
+        e.paths
+            .iter()
+            .any(|p| p.extension().is_some_and(|x| x == "rs") && !p.starts_with(target_dir))
+    })
+}
+
+/// Watches the `.rs` files under `src_path`, ignoring its `target` directory, and 500ms after the
+/// last change recompiles the crate and extracts its call graph. Each successful rebuild is passed
+/// to `on_ready` on a background thread; callers are responsible for any synchronization. Failed
+/// rebuilds are logged and skipped.
+///
+/// # Errors
+/// Returns `Error::IoError` if `src_path` does not exist and `Error::WatchError` if the file
+/// watcher cannot be started.
+pub fn watch_and_recompile<F>(
+    src_path: &Path,
+    opts: &CompileOptions,
+    on_ready: F,
+) -> Result<WatchHandle, Error>
+where
+    F: Fn(CallGraph) + Send + 'static,
+{
+    // Events carry absolute paths, so compare against the canonical target directory.
+    let src_path = src_path.canonicalize()?;
+    let target_dir = src_path.join("target");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&src_path, RecursiveMode::Recursive)?;
+
+    let opts = opts.clone();
+    let thread = std::thread::spawn(move || {
+        while let Ok(event) = rx.recv() {
+            if !is_source_change(&event, &target_dir) {
+                continue;
+            }
+
+            // Debounce: wait until no further source change arrives within the quiet period.
+            let mut deadline = Instant::now() + DEBOUNCE;
+            loop {
+                match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(event) => {
+                        if is_source_change(&event, &target_dir) {
+                            deadline = Instant::now() + DEBOUNCE;
+                        }
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            log::info!("Recompiling: {}", src_path.display());
+            let graph = compile::compile_bitcode(&src_path, &opts)
+                .map_err(Error::from)
+                .and_then(|files| {
+                    analysis::extract_calls_from_files(&files, &AnalysisConfig::default())
+                });
+
+            match graph {
+                Ok(graph) => on_ready(graph),
+                Err(e) => log::error!("{e:?}"),
+            }
+        }
+    });
+
+    Ok(WatchHandle { watcher, thread })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_sources_are_not_changes() {
+        let target_dir = Path::new("/work/crate/target");
+        let event =
+            |path: &str| Ok(notify::Event::new(notify::EventKind::Any).add_path(path.into()));
+
+        assert!(is_source_change(
+            &event("/work/crate/src/lib.rs"),
+            target_dir
+        ));
+        assert!(is_source_change(&event("/work/crate/build.rs"), target_dir));
+        assert!(!is_source_change(
+            &event("/work/crate/target/debug/build/out/bindings.rs"),
+            target_dir
+        ));
+        assert!(!is_source_change(
+            &event("/work/crate/Cargo.lock"),
+            target_dir
+        ));
+    }
+}
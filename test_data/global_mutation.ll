@@ -0,0 +1,25 @@
+; Fixture for detect_global_state_mutations, assembled with `llvm-as global_mutation.ll`.
+; `globals::bump` increments a `static mut` counter which `globals::get` only reads.
+
+@_ZN7globals7COUNTER17h0123456789abcdefE = internal global i64 0, align 8
+
+define void @_ZN7globals4bump17h0123456789abcdefE() {
+start:
+  %0 = load i64, i64* @_ZN7globals7COUNTER17h0123456789abcdefE, align 8
+  %1 = add i64 %0, 1
+  store i64 %1, i64* @_ZN7globals7COUNTER17h0123456789abcdefE, align 8
+  ret void
+}
+
+define i64 @_ZN7globals3get17h0123456789abcdefE() {
+start:
+  %0 = load i64, i64* @_ZN7globals7COUNTER17h0123456789abcdefE, align 8
+  ret i64 %0
+}
+
+define i64 @_ZN7globals4main17h0123456789abcdefE() {
+start:
+  call void @_ZN7globals4bump17h0123456789abcdefE()
+  %0 = call i64 @_ZN7globals3get17h0123456789abcdefE()
+  ret i64 %0
+}